@@ -0,0 +1,278 @@
+use crate::cps_macro::{build_next_step, flatten_none_groups, InternalNames};
+use crate::eager_calls::EagerCalls;
+use crate::macro_matching::{match_bindings, Binding, Bindings};
+use crate::parse_cps_attr::CPSAttr;
+use crate::parse_cps_input::{MacroInput, StackElement};
+use crate::parse_macro_decl::{CPSMacroRule, MacroMatcher};
+use crate::validate_macro_decl::{collect_references, validate_rule, Diagnostics};
+use proc_macro2::{Delimiter, Group, TokenStream, TokenTree};
+use quote::{quote, ToTokens};
+use syn::parse::{ParseStream, Parser};
+use syn::spanned::Spanned;
+use syn::Attribute;
+
+/// The contents of a `cps::eval!` block: an optional `#![cps(...)]` attribute followed by let bindings and a body,
+/// which are treated as a rule of a macro that takes no arguments. Eager calls are lifted as they are in macros.
+struct EvalProgram {
+    attr: CPSAttr,
+    rule: CPSMacroRule,
+}
+
+impl EvalProgram {
+    fn parse(tokens: TokenStream) -> syn::Result<Self> {
+        (|input: ParseStream| {
+            let mut attr = CPSAttr::default();
+            for inner in input.call(Attribute::parse_inner)? {
+                if !inner.path().is_ident("cps") {
+                    return Err(syn::Error::new_spanned(inner, "expected `#![cps(...)]`"));
+                }
+                attr = CPSAttr::parse(inner.meta.require_list()?.tokens.clone())?;
+            }
+
+            let rest: TokenStream = input.parse()?;
+            let mut rule: CPSMacroRule = syn::parse2(quote! { () => #rest })?;
+
+            let names = InternalNames::new(&rest);
+            let (let_bindings, impl_tokens) =
+                EagerCalls::new(&attr, &names).lift_rule(rule.let_bindings, rule.impl_tokens)?;
+            rule.let_bindings = let_bindings;
+            rule.impl_tokens = impl_tokens;
+
+            Ok(Self { attr, rule })
+        })
+        .parse2(tokens)
+    }
+}
+
+/// Transcribes tokens, replacing metavariables with the fragments bound to them and expanding repetitions.
+fn transcribe(tokens: TokenStream, bindings: &Bindings) -> syn::Result<TokenStream> {
+    let mut output = TokenStream::new();
+    let mut tokens = tokens.into_iter().peekable();
+    while let Some(tt) = tokens.next() {
+        match tt {
+            TokenTree::Punct(dollar) if dollar.as_char() == '$' => match tokens.peek().cloned() {
+                // `$$` is an escaped `$`
+                Some(TokenTree::Punct(next)) if next.as_char() == '$' => {
+                    tokens.next();
+                    output.extend([TokenTree::Punct(next)]);
+                }
+                Some(TokenTree::Ident(name)) => match bindings.get(&name.to_string()) {
+                    Some(Binding::Fragment(fragment)) => {
+                        tokens.next();
+                        output.extend(fragment.clone());
+                    }
+                    Some(Binding::Repetition(_)) => {
+                        return Err(syn::Error::new(
+                            name.span(),
+                            format!("variable `{}` is still repeating at this depth", name),
+                        ))
+                    }
+                    None => output.extend([TokenTree::Punct(dollar)]),
+                },
+                Some(TokenTree::Group(repeated)) if repeated.delimiter() == Delimiter::Parenthesis => {
+                    tokens.next();
+                    let separator = match tokens.next() {
+                        Some(TokenTree::Punct(op)) if matches!(op.as_char(), '*' | '+' | '?') => None,
+                        Some(separator) => match tokens.next() {
+                            Some(TokenTree::Punct(op)) if matches!(op.as_char(), '*' | '+' | '?') => Some(separator),
+                            _ => return Err(syn::Error::new(separator.span(), "expected one of `*`, `+` or `?`")),
+                        },
+                        None => return Err(syn::Error::new(repeated.span(), "expected one of `*`, `+` or `?`")),
+                    };
+                    output.extend(transcribe_repetition(repeated, separator, bindings)?);
+                }
+                _ => output.extend([TokenTree::Punct(dollar)]),
+            },
+            TokenTree::Group(g) => {
+                let mut new_group = Group::new(g.delimiter(), transcribe(g.stream(), bindings)?);
+                new_group.set_span(g.span());
+                output.extend([TokenTree::Group(new_group)]);
+            }
+            tt => output.extend([tt]),
+        }
+    }
+    Ok(output)
+}
+
+fn transcribe_repetition(
+    repeated: Group,
+    separator: Option<TokenTree>,
+    bindings: &Bindings,
+) -> syn::Result<TokenStream> {
+    // The repetition is driven by the metavariables inside it that are still repeating
+    let mut references = Vec::new();
+    collect_references(repeated.stream(), &mut references);
+    let mut repeating: Vec<(String, &Vec<Binding>)> = Vec::new();
+    for reference in references {
+        let name = reference.to_string();
+        if let Some(Binding::Repetition(values)) = bindings.get(&name) {
+            if let Some((other, other_values)) = repeating.iter().find(|(_, other)| other.len() != values.len()) {
+                return Err(syn::Error::new(
+                    reference.span(),
+                    format!(
+                        "meta-variable `{}` repeats {} times, but `{}` repeats {} times",
+                        name,
+                        values.len(),
+                        other,
+                        other_values.len()
+                    ),
+                ));
+            }
+            repeating.push((name, values));
+        }
+    }
+    let count = match repeating.first() {
+        Some((_, values)) => values.len(),
+        None => {
+            return Err(syn::Error::new(
+                repeated.span(),
+                "attempted to repeat an expression containing no syntax variables matched as repeating at this depth",
+            ))
+        }
+    };
+
+    let mut output = TokenStream::new();
+    for i in 0..count {
+        if i > 0 {
+            output.extend(separator.clone());
+        }
+        let mut iteration = bindings.clone();
+        for (name, values) in &repeating {
+            iteration.insert(name.clone(), values[i].clone());
+        }
+        output.extend(transcribe(repeated.stream(), &iteration)?);
+    }
+    Ok(output)
+}
+
+fn render_stack(stack: Vec<(Vec<StackElement>, syn::Token![|])>) -> TokenStream {
+    let mut rendered = TokenStream::new();
+    for (part, _) in stack {
+        rendered.extend(quote! { #(#part)* | });
+    }
+    rendered
+}
+
+/// Performs a single step of evaluating an eval block. The first part of the stack holds the results of the let
+/// bindings that have been evaluated so far, most recent first, followed by the block itself.
+fn step(mut m: MacroInput) -> syn::Result<TokenStream> {
+    let (frames, _) = m.stack.remove(0);
+    let (program, results) = frames
+        .split_last()
+        .expect("eval block was missing from the cps stack");
+    let EvalProgram { attr, rule } = EvalProgram::parse(program.lhs.internal.clone())?;
+
+    // Bind the results of the let bindings that have already been evaluated
+    let mut bindings = Bindings::new();
+    for (binding, result) in rule.let_bindings.iter().zip(results.iter().rev()) {
+        let pattern = MacroMatcher {
+            matches: vec![binding.pattern.clone()],
+        };
+        match match_bindings(&pattern, result.lhs.internal.clone()) {
+            Some(new_bindings) => bindings.extend(new_bindings),
+            None => {
+                return Err(syn::Error::new(
+                    binding.pattern.span(),
+                    format!(
+                        "while evaluating cps::eval!, expected something that matches `{}` but got `{}` instead",
+                        pattern.to_token_stream(),
+                        result.lhs.internal
+                    ),
+                ))
+            }
+        }
+    }
+
+    let crate_path = attr.crate_path();
+    let next_stack = render_stack(m.stack);
+
+    // Evaluate the next let binding, continuing with this block once it is done
+    if let Some(binding) = rule.let_bindings.get(results.len()) {
+        let args = transcribe(binding.macro_invocation.tokens.clone(), &bindings)?;
+        let path = &binding.macro_invocation.path;
+        let callee = match binding.macro_name_indirection {
+            Some(dollar) => flatten_none_groups(transcribe(quote! { #dollar #path }, &bindings)?),
+            None => attr.resolve_macro_path(path),
+        };
+        let program = m.program.into_iter();
+        return Ok(quote! {
+            #callee ! { @_cps |:|
+                (#crate_path::eval) #(| #program)* |:|
+                ({ #args }, { #args }) | #(#frames)* | #next_stack
+            }
+        });
+    }
+
+    // All let bindings are evaluated, so the body is the result
+    let body = transcribe(rule.impl_tokens, &bindings)?;
+    let mut program = m.program.into_iter();
+    match program.next() {
+        None => Ok(body),
+        Some(next) => {
+            let program = quote! { #(#program)|* };
+            Ok(build_next_step(next.path, program, body, next_stack))
+        }
+    }
+}
+
+pub fn impl_eval(input: TokenStream) -> TokenStream {
+    let is_continuation = matches!(
+        input.clone().into_iter().next(),
+        Some(TokenTree::Punct(p)) if p.as_char() == '@'
+    );
+
+    let m: MacroInput = if is_continuation {
+        syn::parse2(input).expect("failed to parse CPS macro input")
+    } else {
+        // Check the block before starting to evaluate it. Warnings are items, so can't be emitted in every
+        // position that a block can be used in
+        let program = match EvalProgram::parse(input.clone()) {
+            Ok(program) => program,
+            Err(e) => return e.to_compile_error(),
+        };
+        let mut diagnostics = Diagnostics::default();
+        validate_rule(&program.rule, &mut diagnostics);
+        if let Some(errors) = diagnostics.into_errors() {
+            return errors.to_compile_error();
+        }
+
+        syn::parse2(quote! { @_cps |:| |:| ({ #input }, { #input }) | })
+            .expect("failed to build CPS macro input")
+    };
+
+    step(m).unwrap_or_else(|e| e.to_compile_error())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn mismatched_result() {
+        let res = impl_eval(quote! {
+            @_cps |:| |:| ({ 12 }, { 12 }) ({ let $x:ident = foo!() in { $x } }, { let $x:ident = foo!() in { $x } }) |
+        })
+        .to_string();
+        assert!(
+            res.contains("while evaluating cps::eval!, expected something that matches `$ x : ident` but got `12` instead"),
+            "{}",
+            res
+        );
+    }
+
+    #[test]
+    fn first_step() {
+        let program = quote! {
+            #![cps(crate = "facade")]
+            let $($x:tt)* = cps::stringify!(a) in { $($x)* }
+        };
+        let res = impl_eval(program.clone());
+        let expected = quote! {
+            facade::stringify! { @_cps |:|
+                (facade::eval) |:|
+                ({ a }, { a }) | ({ #program }, { #program }) |
+            }
+        };
+        assert_eq!(res.to_string(), expected.to_string());
+    }
+}
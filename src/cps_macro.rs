@@ -1,10 +1,14 @@
-use std::collections::HashMap;
-
+use crate::eager_calls::EagerCalls;
+use crate::include_tracking::track_included_files;
+use crate::parse_cps_attr::CPSAttr;
 use crate::parse_macro_decl::{begins_with_cps_marker, CPSMacroRule, MacroMatch, MacroMatcher};
-use proc_macro2::{Ident, TokenStream};
+use crate::parse_cps_input::CPS_MARKER_STR;
+use crate::validate_macro_decl::{collect_bindings, collect_match_bindings, validate_rule, Diagnostics};
+use proc_macro2::{Delimiter, Group, Ident, TokenStream, TokenTree};
 use quote::{quote, ToTokens, format_ident};
 use syn::punctuated::Punctuated;
-use syn::{parse_quote, ItemMacro, Token};
+use syn::parse::Parser;
+use syn::{ItemMacro, Token};
 
 fn assert_arm_valid(m: &CPSMacroRule) {
     // Check that initial pattern is not the cps identifier
@@ -14,6 +18,97 @@ fn assert_arm_valid(m: &CPSMacroRule) {
     );
 }
 
+/// Debug rules that catch a let binding result not matching its pattern, along with the patterns that were
+/// expected. Rules are kept in the order that they are first added so that generated macros are deterministic.
+#[derive(Default)]
+struct ErrorRules {
+    rules: Vec<(String, MacroMatcher, Vec<MacroMatcher>)>,
+}
+
+impl ErrorRules {
+    fn add(&mut self, error_match: MacroMatcher, mut expected_patterns: Vec<MacroMatcher>) {
+        let key = error_match.to_token_stream().to_string();
+        match self.rules.iter_mut().find(|(k, _, _)| *k == key) {
+            Some((_, _, expected)) => expected.append(&mut expected_patterns),
+            None => self.rules.push((key, error_match, expected_patterns)),
+        }
+    }
+
+    fn append(&mut self, other: ErrorRules) {
+        for (_, error_match, expected_patterns) in other.rules {
+            self.add(error_match, expected_patterns);
+        }
+    }
+}
+
+/// The names of metavariables used by generated rules. Every name starts with a prefix that no metavariable in
+/// the user's macro starts with, so generated names can never clash with user names.
+pub struct InternalNames {
+    prefix: String,
+}
+
+impl InternalNames {
+    pub fn new(user_tokens: &TokenStream) -> Self {
+        let mut user_names = Vec::new();
+        collect_metavariable_names(user_tokens.clone(), &mut user_names);
+
+        let mut prefix = CPS_MARKER_STR.to_owned();
+        while user_names.iter().any(|name| name.starts_with(&prefix)) {
+            prefix += "_";
+        }
+
+        Self { prefix }
+    }
+
+    pub fn ident(&self, name: &str) -> Ident {
+        format_ident!("{}_{}", self.prefix, name)
+    }
+}
+
+fn collect_metavariable_names(tokens: TokenStream, names: &mut Vec<String>) {
+    let mut after_dollar = false;
+    for tt in tokens {
+        match &tt {
+            TokenTree::Ident(i) if after_dollar => names.push(i.to_string()),
+            TokenTree::Group(g) => collect_metavariable_names(g.stream(), names),
+            _ => {}
+        }
+        after_dollar = matches!(&tt, TokenTree::Punct(p) if p.as_char() == '$');
+    }
+}
+
+/// Replaces every `$$` in a rule body with `$dollar`, so that the body can contain macro definitions with their
+/// own metavariables and repetitions. Returns whether any replacements were made.
+fn escape_dollars(tokens: TokenStream, dollar: &Ident) -> (TokenStream, bool) {
+    let mut escaped = false;
+    let mut output = Vec::new();
+    let mut tokens = tokens.into_iter().peekable();
+    while let Some(tt) = tokens.next() {
+        match tt {
+            TokenTree::Punct(p) if p.as_char() == '$' => {
+                let next_is_dollar = matches!(tokens.peek(), Some(TokenTree::Punct(next)) if next.as_char() == '$');
+                output.push(TokenTree::Punct(p));
+                if next_is_dollar {
+                    let mut dollar = dollar.clone();
+                    dollar.set_span(tokens.next().expect("peeked").span());
+                    output.push(TokenTree::Ident(dollar));
+                    escaped = true;
+                }
+            }
+            TokenTree::Group(g) => {
+                let (stream, inner_escaped) = escape_dollars(g.stream(), dollar);
+                let mut new_group = Group::new(g.delimiter(), stream);
+                new_group.set_span(g.span());
+                output.push(TokenTree::Group(new_group));
+                escaped |= inner_escaped;
+            }
+            tt => output.push(tt),
+        }
+    }
+
+    (output.into_iter().collect(), escaped)
+}
+
 pub fn build_next_step(
     next_head: impl ToTokens,
     next_program: impl ToTokens,
@@ -28,19 +123,26 @@ pub fn build_next_step(
     }
 }
 
-#[allow(clippy::type_complexity)]
 fn add_cps(
+    attr: &CPSAttr,
+    names: &InternalNames,
     macro_name: &Ident,
     arm: CPSMacroRule,
-) -> (
-    Vec<CPSMacroRule>,
-    HashMap<String, (MacroMatcher, Vec<MacroMatcher>)>,
-) {
+) -> (Vec<CPSMacroRule>, ErrorRules) {
     let pattern = arm.pattern.clone();
-    let impl_tokens = arm.impl_tokens;
+    let (impl_tokens, escaped) = escape_dollars(arm.impl_tokens, &names.ident("dollar"));
 
     let mut output_cases = Vec::new();
-    let mut output_debug_cases = HashMap::new();
+    let mut output_debug_cases = ErrorRules::default();
+
+    let next_head = names.ident("next_head");
+    let next_tail = names.ident("next_tail");
+    let next = names.ident("next");
+    let stack = names.ident("stack");
+    let args = names.ident("args");
+    let dollar = names.ident("dollar");
+    let unexpected = names.ident("unexpected");
+    let unexpected_copy = names.ident("unexpected_copy");
 
     // Functions can be evaluated in several contexts:
     // 1. Base Case - they are the last function to execute and all of their bindings have been evaluated
@@ -54,44 +156,96 @@ fn add_cps(
         .rev()
         .map(|lb| lb.pattern.clone())
         .collect::<Vec<MacroMatch>>();
-    let result_pattern_duds: Vec<_> = result_patterns.iter().enumerate().map(|(i, _)| format_ident!("_cps_res{}", i)).collect();
+    let result_pattern_duds: Vec<_> = (0..result_patterns.len()).map(|i| names.ident(&format!("res{}", i))).collect();
+    let base_frames = quote! {
+        #( ({ #result_patterns }, { $($ #result_pattern_duds :tt)* }) )* ({ #pattern }, { $($ #args :tt)* })
+    };
+    let base_frame_copies = quote! {
+        #( ({ $($ #result_pattern_duds)* }, { $($ #result_pattern_duds)* }) )* ({ $($ #args)* }, { $($ #args)* })
+    };
+    let next_step = build_next_step(
+        quote! { $($ #next_head)* },
+        quote! { $( ( $($ #next_tail)* ) )|* },
+        &impl_tokens,
+        quote! { $($ #stack)* },
+    );
+
+    // Bodies containing `$$` can only be expanded by a rule that has a `$` token bound, so the base cases
+    // first pass a `$` back to this macro, which is then matched by an escaped copy of the base cases
+    let (base_body, inner_base_body) = if escaped {
+        (
+            quote! { #macro_name ! { @_cps ($) |:| |:| #base_frame_copies | } },
+            quote! { #macro_name ! { @_cps ($) |:| ( $($ #next_head)* ) $(| ( $($ #next_tail)* ) )* |:| #base_frame_copies | $($ #stack)* } },
+        )
+    } else {
+        (impl_tokens.clone(), next_step.clone())
+    };
+
     let base_case: CPSMacroRule = syn::parse2(quote! {
-        (@_cps |:|  |:| #( ({ #result_patterns }, { $($ #result_pattern_duds :tt)* }) )* ({ #pattern }, { $($_cps_dud_pattern:tt)* }) | ) => {
-            #impl_tokens
+        (@_cps |:|  |:| #base_frames | ) => {
+            #base_body
         }
     })
     .expect("could not build cps base case");
     output_cases.push(base_case);
 
     // Above is a special case of this for reduced macro recursion depth
-    let next_step = build_next_step(
-        quote! { $_cps_next_head },
-        quote! { $( ( $_cps_next_tail ) )|* },
-        impl_tokens,
-        quote! { $($_cps_stack)* },
-    );
     let inner_base_case: CPSMacroRule = syn::parse2(quote!{
-        (@_cps |:| ( $_cps_next_head:tt ) $(| ( $_cps_next_tail:tt ) )* |:| #( ({ #result_patterns }, { $($ #result_pattern_duds :tt)* }) )* ({ #pattern }, { $($_p:tt)* }) | $($_cps_stack:tt)*) => {
-            #next_step
+        (@_cps |:| ( $($ #next_head :tt)* ) $(| ( $($ #next_tail :tt)* ) )* |:| #base_frames | $($ #stack :tt)*) => {
+            #inner_base_body
         }
     }).expect("could not build cps inner base case");
     output_cases.push(inner_base_case);
 
+    if escaped {
+        let escaped_base_case: CPSMacroRule = syn::parse2(quote! {
+            (@_cps ($ #dollar :tt) |:|  |:| #base_frames | ) => {
+                #impl_tokens
+            }
+        })
+        .expect("could not build cps escaped base case");
+        output_cases.push(escaped_base_case);
+
+        let escaped_inner_base_case: CPSMacroRule = syn::parse2(quote!{
+            (@_cps ($ #dollar :tt) |:| ( $($ #next_head :tt)* ) $(| ( $($ #next_tail :tt)* ) )* |:| #base_frames | $($ #stack :tt)*) => {
+                #next_step
+            }
+        }).expect("could not build cps escaped inner base case");
+        output_cases.push(escaped_inner_base_case);
+    }
+
     // Create a pattern for each intermediate step as earlier results may be used in later executions
+    let mut bound_fragments = Vec::new();
+    collect_bindings(&arm.pattern, &mut bound_fragments);
     let mut acc_result_patterns = Vec::new();
     let mut acc_result_tts = Vec::new();
     let mut acc_result_clones = Vec::new();
     for (i, binding) in arm.let_bindings.iter().enumerate() {
-        let path_indirection = binding.macro_name_indirection;
-        let binding_macro_path = binding.macro_invocation.path.clone();
         let binding_macro_args = binding.macro_invocation.tokens.clone();
+        let call_body = quote! { @_cps |:|
+            ( #macro_name ) $(| ( $($ #next)* ) )* |:|
+            ({ #binding_macro_args }, { #binding_macro_args }) | #( ({ #acc_result_clones }, { #acc_result_clones }) )* ({$($ #args)*}, {$($ #args)*}) | $($ #stack)*
+        };
+        let call = match (binding.macro_name_indirection, binding.macro_invocation.path.segments.first()) {
+            // Paths captured by a `path` fragment can't be invoked directly, so are flattened by a proc macro first
+            (Some(dollar), Some(first)) if bound_fragments.iter().any(|(name, frag)| *name == &first.ident && *frag == "path") => {
+                let crate_path = attr.crate_path();
+                let path = &binding.macro_invocation.path;
+                quote! {
+                    #crate_path::__call_path! { (#dollar #path) { #call_body } }
+                }
+            }
+            (path_indirection, _) => {
+                let binding_macro_path = attr.resolve_macro_path(&binding.macro_invocation.path);
+                quote! {
+                    #path_indirection #binding_macro_path ! { #call_body }
+                }
+            }
+        };
         // Successful inner case
         let inter_case: CPSMacroRule = syn::parse2(quote!{
-            (@_cps |:| $( ( $_cps_next:tt ) )|* |:| #( ({ #acc_result_patterns }, { #acc_result_tts }) )* ({ #pattern }, {$($_cps_arg:tt)*}) | $($_cps_stack:tt)*) => {
-                #path_indirection #binding_macro_path ! { @_cps |:|
-                    ( #macro_name ) $(| ( $_cps_next ) )* |:|
-                    ({ #binding_macro_args }, { #binding_macro_args }) | #( ({ #acc_result_clones }, { #acc_result_clones }) )* ({$($_cps_arg)*}, {$($_cps_arg)*}) | $($_cps_stack)*
-                }
+            (@_cps |:| $( ( $($ #next :tt)* ) )|* |:| #( ({ #acc_result_patterns }, { #acc_result_tts }) )* ({ #pattern }, {$($ #args :tt)*}) | $($ #stack :tt)*) => {
+                #call
             }
         }).expect("could not build cps inter case");
         output_cases.push(inter_case);
@@ -105,64 +259,135 @@ fn add_cps(
             })
             .collect::<Vec<_>>();
         valid_patterns.push(pattern.clone());
-        let valid_pattern_duds: Vec<_> = valid_patterns.iter().enumerate().map(|(i, _)| format_ident!("_cps_res{}", i)).collect();
+        let valid_pattern_duds: Vec<_> = (0..valid_patterns.len()).map(|i| names.ident(&format!("res{}", i))).collect();
         let expected_pattern = valid_patterns.remove(0);
         let invalid_match: MacroMatcher = syn::parse2(quote!{
-            @_cps |:| $( ( $_cps_next:tt ) )|* |:| ({ $($unexpected:tt)* }, { $($_cps_un2:tt)* }) #( ({ #valid_patterns }, { $($ #valid_pattern_duds :tt)* }) )* | $($_cps_stack:tt)*
+            @_cps |:| $( ( $($ #next :tt)* ) )|* |:| ({ $($ #unexpected :tt)* }, { $($ #unexpected_copy :tt)* }) #( ({ #valid_patterns }, { $($ #valid_pattern_duds :tt)* }) )* | $($ #stack :tt)*
         }).expect("could not build cps inter debug match");
-        output_debug_cases
-            .entry(invalid_match.to_token_stream().to_string())
-            .or_insert((invalid_match, vec![]))
-            .1
-            .push(expected_pattern);
+        output_debug_cases.add(invalid_match, vec![expected_pattern]);
 
         let result_pattern = result_patterns
             .pop()
             .expect("different number of matches to let bindings");
 
-        let tt_ident = format_ident!("_cps_arg{}", i);
+        let tt_ident = names.ident(&format!("arg{}", i));
         acc_result_tts.insert(0, quote!{$($ #tt_ident :tt)*});
         acc_result_clones.insert(0, quote!{$($ #tt_ident)*});
         acc_result_patterns.insert(0, result_pattern);
+        collect_match_bindings(&binding.pattern, &mut bound_fragments);
     }
 
     (output_cases, output_debug_cases)
 }
 
-pub fn impl_cps(_attr: TokenStream, m: ItemMacro) -> TokenStream {
+/// Flattens a path captured by a `path` fragment so that it can be used as the name of a macro invocation.
+pub fn impl_call_path(input: TokenStream) -> TokenStream {
+    let mut tokens = input.into_iter();
+    let (path, body) = match (tokens.next(), tokens.next(), tokens.next()) {
+        (Some(TokenTree::Group(path)), Some(TokenTree::Group(body)), None) => (path, body),
+        _ => panic!("invalid input to __call_path - this is a bug with the cps crate and should be reported here: https://github.com/LucentFlux/CPS/issues"),
+    };
+
+    let path = flatten_none_groups(path.stream());
+    let body = body.stream();
+    quote! {
+        #path ! { #body }
+    }
+}
+
+pub fn flatten_none_groups(tokens: TokenStream) -> TokenStream {
+    tokens
+        .into_iter()
+        .flat_map(|tt| match tt {
+            TokenTree::Group(g) if g.delimiter() == Delimiter::None => flatten_none_groups(g.stream()),
+            tt => tt.into_token_stream(),
+        })
+        .collect()
+}
+
+pub fn is_macro_rules(m: &ItemMacro) -> bool {
+    m.mac
+        .path
+        .segments
+        .last()
+        .map(|segment| segment.ident == "macro_rules")
+        .unwrap_or(false)
+}
+
+pub fn parse_rules(tokens: TokenStream) -> syn::Result<Vec<CPSMacroRule>> {
+    let rules = Punctuated::<CPSMacroRule, Token![;]>::parse_terminated.parse2(tokens)?;
+    Ok(rules.into_iter().collect())
+}
+
+pub fn impl_cps(attr: TokenStream, m: ItemMacro) -> TokenStream {
+    let attr = match CPSAttr::parse(attr) {
+        Ok(attr) => attr,
+        Err(e) => return e.to_compile_error(),
+    };
+
     // Check we're being applied to a macro_rules! definition
     let err = "expected a macro_rules! macro definition";
-    assert_eq!(
-        "macro_rules",
-        m.mac.path.segments.last().expect(err).ident.to_string(),
-        "{}",
-        err
-    );
+    assert!(is_macro_rules(&m), "{}", err);
     let macro_name = m.ident.expect(err);
 
     // Parse rules
     let rules_tokens = m.mac.tokens;
-    let rules: Punctuated<CPSMacroRule, Token![;]> = parse_quote! { #rules_tokens };
+    let mut rules = match parse_rules(rules_tokens.clone()) {
+        Ok(rules) => rules,
+        Err(e) => return e.to_compile_error(),
+    };
+
+    // Choose names for our metavariables that are distinct from the user's
+    let names = InternalNames::new(&rules_tokens);
+
+    // Turn eager calls into regular let bindings
+    let mut eager_calls = EagerCalls::new(&attr, &names);
+    for rule in rules.iter_mut() {
+        let let_bindings = std::mem::take(&mut rule.let_bindings);
+        let impl_tokens = std::mem::take(&mut rule.impl_tokens);
+        match eager_calls.lift_rule(let_bindings, impl_tokens) {
+            Ok((let_bindings, impl_tokens)) => {
+                rule.let_bindings = let_bindings;
+                rule.impl_tokens = impl_tokens;
+            }
+            Err(e) => return e.to_compile_error(),
+        }
+    }
 
     // Check that all rules are of valid form
+    let mut diagnostics = Diagnostics::default();
     for rule in rules.iter() {
         assert_arm_valid(rule);
+        validate_rule(rule, &mut diagnostics);
     }
+    let unexpected = names.ident("unexpected");
+    let unexpected_copy = names.ident("unexpected_copy");
+    let call_stack = names.ident("call_stack");
+    let data_stack = names.ident("data_stack");
+    let everything = names.ident("everything");
+    let input = names.ident("input");
+
+    // Files read by include builtins need to be tracked by the crate defining the macro
+    let tracking = track_included_files(&attr, &rules);
+
+    // Generated rules call builtins with braces, but clippy expects `format!` to be called with parentheses
+    let calls_format = rules
+        .iter()
+        .flat_map(|rule| rule.let_bindings.iter())
+        .any(|binding| binding.macro_invocation.path.segments.last().is_some_and(|last| last.ident == "format"));
+    let allow_braces = calls_format.then(|| quote! { #[allow(clippy::nonstandard_macro_braces)] });
 
     // Add cps to all rules
     let mut new_rules = Vec::new();
-    let mut error_rules = HashMap::new();
+    let mut error_rules = ErrorRules::default();
     for rule in rules {
-        let (mut new_cps_rules, new_error_rules) = add_cps(&macro_name, rule);
+        let (mut new_cps_rules, new_error_rules) = add_cps(&attr, &names, &macro_name, rule);
         new_rules.append(&mut new_cps_rules);
-
-        for (s, (error_match, mut expected_patterns)) in new_error_rules {
-            error_rules.entry(s).or_insert((error_match, vec![])).1.append(&mut expected_patterns);
-        }
+        error_rules.append(new_error_rules);
     }
 
     // Collate same errors into messages
-    let error_rules = error_rules.into_iter().map(|(_, (error_match, expected_patterns))| {
+    let error_rules = error_rules.rules.into_iter().map(|(_, error_match, expected_patterns)| {
         let err_msg = if expected_patterns.len() == 1 {
             let expected_pattern = expected_patterns.first().expect("len is 1");
             format!(
@@ -171,8 +396,11 @@ pub fn impl_cps(_attr: TokenStream, m: ItemMacro) -> TokenStream {
                 expected_pattern.to_token_stream()
             )
         } else {
-            let parts = 
-            expected_patterns.iter().map(|expected_pattern| format!("`{}`", expected_pattern.to_token_stream())).fold("".to_owned(), |a, b| a + " or " + &b);
+            let parts = expected_patterns
+                .iter()
+                .map(|expected_pattern| format!("`{}`", expected_pattern.to_token_stream()))
+                .collect::<Vec<_>>()
+                .join(" or ");
             format!(
                 "while evaluating macro {}, expected something that matches one of {} but got `",
                 macro_name,
@@ -182,7 +410,7 @@ pub fn impl_cps(_attr: TokenStream, m: ItemMacro) -> TokenStream {
 
         syn::parse2::<CPSMacroRule>(quote!{
             (#error_match) => {
-                std::compile_error!(std::concat!(#err_msg, std::stringify!($($unexpected)*) ,"` instead"));
+                ::core::compile_error!(::core::concat!(#err_msg, ::core::stringify!($($ #unexpected)*) ,"` instead"));
             }
         }).expect("could not build cps inter debug case")
     }).collect::<Vec<_>>();
@@ -190,22 +418,22 @@ pub fn impl_cps(_attr: TokenStream, m: ItemMacro) -> TokenStream {
     // Add some fallback CPS rules that can help with debugging
     let fallback_rules = quote! {
         // If nothing else matches
-        (@_cps |:| $(($call_stack:tt))|* |:| ({ $($unexpected:tt)* }, { $($_un2:tt)* }) $($data_stack:tt)* ) => {
-            std::compile_error!(concat!("cannot match `", stringify!($($unexpected)*), "`"));
+        (@_cps |:| $(( $($ #call_stack :tt)* ))|* |:| ({ $($ #unexpected :tt)* }, { $($ #unexpected_copy :tt)* }) $($ #data_stack :tt)* ) => {
+            ::core::compile_error!(::core::concat!("cannot match `", ::core::stringify!($($ #unexpected)*), "`"));
         };
         // Base case but wrong arguments
         (@_cps |:|  |:|  |) => {
-            std::compile_error!("base case has no result - this is a bug with the cps crate and should be reported here: https://github.com/LucentFlux/CPS/issues");
+            ::core::compile_error!("base case has no result - this is a bug with the cps crate and should be reported here: https://github.com/LucentFlux/CPS/issues");
         };
-        (@_cps $($everything:tt)*) => {
-            std::compile_error!(concat!("cps macro evaluation resulted in an invalid state: `", stringify!($($everything)*), "` - this is a bug with the cps crate and should be reported here: https://github.com/LucentFlux/CPS/issues"));
+        (@_cps $($ #everything :tt)*) => {
+            ::core::compile_error!(::core::concat!("cps macro evaluation resulted in an invalid state: `", ::core::stringify!($($ #everything)*), "` - this is a bug with the cps crate and should be reported here: https://github.com/LucentFlux/CPS/issues"));
         };
     };
 
     // Create an entry point from outside a cps context
     let entry = quote! {
-        ($($input:tt)*) => {
-            #macro_name ! { @_cps |:|  |:| ({ $($input)* }, { $($input)* }) | }
+        ($($ #input :tt)*) => {
+            #macro_name ! { @_cps |:|  |:| ({ $($ #input)* }, { $($ #input)* }) | }
         };
     };
 
@@ -215,13 +443,203 @@ pub fn impl_cps(_attr: TokenStream, m: ItemMacro) -> TokenStream {
     let semi = m.semi_token;
     let rebuilt = quote! {
         #(#attrs)*
+        #allow_braces
         #path ! #macro_name {
             #(#new_rules ;)*
             #(#error_rules ;)*
             #fallback_rules
             #entry
         } #semi
+
+        #diagnostics
+        #tracking
     };
 
     rebuilt
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::path::PathBuf;
+
+    /// Renders a rewritten macro with one rule per line so that snapshot diffs are readable.
+    fn render(expanded: TokenStream) -> String {
+        let m: ItemMacro = syn::parse2(expanded).expect("rewritten macro was not a macro definition");
+
+        let mut rendered = format!("{} ! {} {{\n", m.mac.path.to_token_stream(), m.ident.expect("macro name"));
+        let mut rule = TokenStream::new();
+        for tt in m.mac.tokens {
+            let is_separator = matches!(&tt, proc_macro2::TokenTree::Punct(p) if p.as_char() == ';');
+            rule.extend([tt]);
+            if is_separator {
+                rendered += &format!("    {}\n", rule);
+                rule = TokenStream::new();
+            }
+        }
+        if !rule.is_empty() {
+            rendered += &format!("    {}\n", rule);
+        }
+        rendered + "}\n"
+    }
+
+    /// Compares the rewritten macro to the snapshot in `tests/snapshots`. Set `CPS_UPDATE_SNAPSHOTS=1` to
+    /// overwrite snapshots with the current output instead.
+    fn assert_snapshot(name: &str, attr: TokenStream, m: TokenStream) {
+        let m: ItemMacro = syn::parse2(m).expect("test input was not a macro definition");
+        let rendered = render(impl_cps(attr.clone(), m.clone()));
+
+        // Generation must not depend on anything other than the input
+        for _ in 0..4 {
+            assert_eq!(rendered, render(impl_cps(attr.clone(), m.clone())));
+        }
+
+        let path = PathBuf::from(env!("CARGO_MANIFEST_DIR"))
+            .join("tests")
+            .join("snapshots")
+            .join(format!("{}.snap", name));
+        if std::env::var_os("CPS_UPDATE_SNAPSHOTS").is_some() {
+            std::fs::write(&path, &rendered).expect("could not write snapshot");
+            return;
+        }
+        let expected = std::fs::read_to_string(&path)
+            .unwrap_or_else(|_| panic!("missing snapshot {}, run with CPS_UPDATE_SNAPSHOTS=1 to create it", path.display()));
+        assert_eq!(expected, rendered, "rewritten macro does not match snapshot {}", path.display());
+    }
+
+    #[test]
+    fn braces_allowed_for_format() {
+        let allows_braces = |m: TokenStream| {
+            let m: ItemMacro = syn::parse2(m).expect("test input was not a macro definition");
+            impl_cps(quote! {}, m).to_string().contains("allow (clippy :: nonstandard_macro_braces)")
+        };
+        assert!(allows_braces(quote! {
+            macro_rules! macro1 {
+                ($x:tt) => let $y:tt = cps::format!("{}", $x) in { $y };
+            }
+        }));
+        assert!(!allows_braces(quote! {
+            macro_rules! macro1 {
+                ($x:tt) => let $y:tt = cps::stringify!($x) in { $y };
+            }
+        }));
+    }
+
+    #[test]
+    fn snapshot_unmodified() {
+        assert_snapshot(
+            "unmodified",
+            quote! {},
+            quote! {
+                macro_rules! macro1 {
+                    () => { "Empty Call" };
+                    (a) => { 1 };
+                    (@ident $i:ident) => { $i };
+                }
+            },
+        );
+    }
+
+    #[test]
+    fn snapshot_single_binding() {
+        assert_snapshot(
+            "single_binding",
+            quote! {},
+            quote! {
+                macro_rules! macro1 {
+                    () => { BaseCase };
+
+                    (stringify) =>
+                    let $x:tt = macro1!() in
+                    {
+                        stringify!($x)
+                    };
+                }
+            },
+        );
+    }
+
+    #[test]
+    fn snapshot_collated_errors() {
+        assert_snapshot(
+            "collated_errors",
+            quote! {},
+            quote! {
+                macro_rules! macro1 {
+                    (a) => { CaseA };
+                    (b) => { CaseB };
+
+                    (first $x:ident) =>
+                    let $y:ident = macro1!(a) in
+                    let $z:literal = cps::stringify!($y) in
+                    {
+                        $z
+                    };
+
+                    (second $($x:tt)*) =>
+                    let $y:ident = macro1!(b) in
+                    let $($z:tt)* = macro1!($y) in
+                    {
+                        stringify!($($z)*)
+                    };
+                }
+            },
+        );
+    }
+
+    #[test]
+    fn snapshot_indirection_and_crate_path() {
+        assert_snapshot(
+            "indirection_and_crate_path",
+            quote! { crate = "facade" },
+            quote! {
+                #[macro_export]
+                macro_rules! macro1 {
+                    ($cont:ident) =>
+                    let $x:tt = $cont!() in
+                    let $y:tt = cps::stringify!($x) in
+                    {
+                        $y
+                    };
+                }
+            },
+        );
+    }
+
+    #[test]
+    fn snapshot_escaped_body() {
+        assert_snapshot(
+            "escaped_body",
+            quote! {},
+            quote! {
+                macro_rules! macro1 {
+                    ($name:ident, $_cps_stack:tt) =>
+                    let $x:tt = macro1!() in
+                    {
+                        macro_rules! $name {
+                            ($$($$y:tt)*) => { $x $_cps_stack $$($$y)* };
+                        }
+                    };
+                }
+            },
+        );
+    }
+
+    #[test]
+    fn snapshot_path_indirection() {
+        assert_snapshot(
+            "path_indirection",
+            quote! {},
+            quote! {
+                macro_rules! macro1 {
+                    ($cont:path) =>
+                    let $x:tt = $cont!() in
+                    let $y:tt = $crate::helper!($x) in
+                    {
+                        $y
+                    };
+                }
+            },
+        );
+    }
+}
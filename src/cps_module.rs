@@ -0,0 +1,188 @@
+use crate::cps_macro::{impl_cps, is_macro_rules, parse_rules};
+use crate::macro_matching::{could_match, InvocationToken};
+use crate::parse_macro_decl::CPSMacroRule;
+use crate::validate_macro_decl::Diagnostics;
+use proc_macro2::{Ident, TokenStream};
+use quote::{quote, ToTokens};
+use syn::spanned::Spanned;
+use syn::{Item, ItemMacro, ItemMod};
+
+/// Whether an attribute is the `cps` attribute. Macros inside a `#[cps]` module are already transformed, so this is
+/// only used to give them their own arguments.
+fn is_cps_attr(attr: &syn::Attribute) -> bool {
+    attr.path()
+        .segments
+        .last()
+        .map(|segment| segment.ident == "cps")
+        .unwrap_or(false)
+}
+
+/// Finds the arguments given to a `cps` attribute on a macro inside a `#[cps]` module, which are used for that macro
+/// instead of the arguments given to the module.
+fn macro_cps_args(cps_attrs: &[syn::Attribute]) -> syn::Result<Option<TokenStream>> {
+    let mut args = None;
+    for attr in cps_attrs {
+        match &attr.meta {
+            syn::Meta::Path(_) => {}
+            syn::Meta::List(list) if args.is_none() => args = Some(list.tokens.clone()),
+            syn::Meta::List(list) => {
+                return Err(syn::Error::new(list.span(), "duplicate cps attribute arguments"))
+            }
+            syn::Meta::NameValue(meta) => {
+                return Err(syn::Error::new(meta.span(), "expected `#[cps]` or `#[cps(...)]`"))
+            }
+        }
+    }
+    Ok(args)
+}
+
+/// Checks that every let binding which invokes a sibling macro by name could match one of that macro's rules.
+fn check_invocations(
+    macros: &[(Ident, Vec<CPSMacroRule>)],
+    diagnostics: &mut Diagnostics,
+) {
+    for (_, rules) in macros {
+        for binding in rules.iter().flat_map(|rule| &rule.let_bindings) {
+            if binding.macro_name_indirection.is_some() {
+                continue;
+            }
+            let target = match binding.macro_invocation.path.get_ident() {
+                Some(target) => target,
+                None => continue,
+            };
+            let target_rules = match macros.iter().find(|(name, _)| name == target) {
+                Some((_, target_rules)) => target_rules,
+                None => continue,
+            };
+            let args = match InvocationToken::from_tokens(binding.macro_invocation.tokens.clone()) {
+                Some(args) => args,
+                None => continue,
+            };
+
+            if !target_rules.iter().any(|rule| could_match(&rule.pattern, &args)) {
+                diagnostics.error(
+                    binding.macro_invocation.span(),
+                    format!(
+                        "no rules of macro `{}` match `{}`",
+                        target,
+                        binding.macro_invocation.tokens
+                    ),
+                );
+            }
+        }
+    }
+}
+
+pub fn impl_cps_mod(attr: TokenStream, m: ItemMod) -> TokenStream {
+    let (brace, items) = match m.content {
+        Some(content) => content,
+        None => {
+            return syn::Error::new(m.ident.span(), "cps modules must have their contents inline")
+                .to_compile_error()
+        }
+    };
+
+    // Find the CPS macros that are siblings in this module
+    let mut macros = Vec::new();
+    for item in &items {
+        if let Item::Macro(mac) = item {
+            if let (true, Some(name)) = (is_macro_rules(mac), &mac.ident) {
+                if let Ok(rules) = parse_rules(mac.mac.tokens.clone()) {
+                    macros.push((name.clone(), rules));
+                }
+            }
+        }
+    }
+
+    let mut diagnostics = Diagnostics::default();
+    check_invocations(&macros, &mut diagnostics);
+
+    // Transform every macro in the module
+    let items = items.into_iter().map(|item| match item {
+        Item::Macro(mac) if is_macro_rules(&mac) && mac.ident.is_some() => {
+            let ItemMacro { attrs, ident, mac, semi_token } = mac;
+            let (cps_attrs, attrs): (Vec<_>, Vec<_>) = attrs.into_iter().partition(is_cps_attr);
+            let attr = match macro_cps_args(&cps_attrs) {
+                Ok(Some(macro_attr)) => macro_attr,
+                Ok(None) => attr.clone(),
+                Err(e) => return e.to_compile_error(),
+            };
+            impl_cps(attr, ItemMacro { attrs, ident, mac, semi_token })
+        }
+        item => item.into_token_stream(),
+    });
+
+    let ItemMod {
+        attrs,
+        vis,
+        unsafety,
+        mod_token,
+        ident,
+        ..
+    } = m;
+    let (outer_attrs, inner_attrs): (Vec<_>, Vec<_>) = attrs
+        .into_iter()
+        .partition(|attr| matches!(attr.style, syn::AttrStyle::Outer));
+    let mut content = TokenStream::new();
+    brace.surround(&mut content, |content| {
+        content.extend(quote! { #(#inner_attrs)* });
+        content.extend(items)
+    });
+    quote! {
+        #(#outer_attrs)*
+        #vis #unsafety #mod_token #ident #content
+
+        #diagnostics
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn unmatched_invocation_reported() {
+        let m: ItemMod = syn::parse2(quote! {
+            mod my_macros {
+                macro_rules! macro1 {
+                    (a) => { CaseA };
+                }
+
+                macro_rules! macro2 {
+                    () =>
+                    let $x:tt = macro1!(b) in
+                    { $x };
+                }
+            }
+        })
+        .expect("test module did not parse");
+
+        let res = impl_cps_mod(TokenStream::new(), m).to_string();
+        assert!(res.contains("no rules of macro `macro1` match `b`"), "{}", res);
+    }
+
+    #[test]
+    fn macro_attribute_arguments_used() {
+        let m: ItemMod = syn::parse2(quote! {
+            mod my_macros {
+                #[cps(crate = "facade::cps")]
+                macro_rules! macro1 {
+                    () =>
+                    let $x:tt = cps::concat!("a") in
+                    { $x };
+                }
+
+                macro_rules! macro2 {
+                    () =>
+                    let $x:tt = cps::concat!("b") in
+                    { $x };
+                }
+            }
+        })
+        .expect("test module did not parse");
+
+        let res = impl_cps_mod(TokenStream::new(), m).to_string();
+        assert_eq!(res.matches("facade :: cps :: concat !").count(), 1, "{}", res);
+        assert!(res.contains("{ cps :: concat ! { @ _cps"), "{}", res);
+    }
+}
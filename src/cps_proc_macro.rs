@@ -10,7 +10,7 @@ pub trait CPSProcMacro {
     type Input: Parse;
     type Output: ToTokens;
 
-    fn step(inp: Self::Input) -> Self::Output;
+    fn step(inp: Self::Input) -> syn::Result<Self::Output>;
 }
 
 pub fn perform_macro<M: CPSProcMacro>(item: TokenStream) -> TokenStream {
@@ -23,8 +23,11 @@ pub fn perform_macro<M: CPSProcMacro>(item: TokenStream) -> TokenStream {
     let arg = syn::parse2(arg.lhs.internal.clone().into_token_stream())
         .expect("failed to parse CPS macro input item");
 
-    // Evaluate
-    let res = M::step(arg);
+    // Evaluate, abandoning the rest of the program on failure
+    let res = match M::step(arg) {
+        Ok(res) => res,
+        Err(e) => return e.to_compile_error(),
+    };
 
     // If the program is done, emit the result
     let next_call = match m.program.first() {
@@ -37,7 +40,7 @@ pub fn perform_macro<M: CPSProcMacro>(item: TokenStream) -> TokenStream {
 
             return res.to_token_stream();
         }
-        Some(v) => v.ident.clone(),
+        Some(v) => v.path.clone(),
     };
 
     let mut remaining_calls: Punctuated<_, Token![|]> = Punctuated::new();
@@ -0,0 +1,267 @@
+use crate::cps_macro::InternalNames;
+use crate::parse_cps_attr::CPSAttr;
+use crate::parse_macro_decl::LetBinding;
+use proc_macro2::{Group, TokenStream, TokenTree};
+use quote::quote;
+
+/// Lifts `cps::eager!(...)` calls out of let binding arguments and rule bodies into hidden let bindings, so that
+/// they are evaluated before the tokens that contain them.
+pub struct EagerCalls<'a> {
+    attr: &'a CPSAttr,
+    names: &'a InternalNames,
+    count: usize,
+}
+
+impl<'a> EagerCalls<'a> {
+    pub fn new(attr: &'a CPSAttr, names: &'a InternalNames) -> Self {
+        Self { attr, names, count: 0 }
+    }
+
+    /// Lifts eager calls out of a rule's let bindings and body. Calls are evaluated in source order, with calls
+    /// nested inside other calls evaluated first, and calls inside a let binding's arguments evaluated just before
+    /// that let binding. Let binding arguments may also mark calls as eager with `name!!(...)`.
+    pub fn lift_rule(
+        &mut self,
+        let_bindings: Vec<LetBinding>,
+        body: TokenStream,
+    ) -> syn::Result<(Vec<LetBinding>, TokenStream)> {
+        let mut lifted = Vec::new();
+        for mut binding in let_bindings {
+            binding.macro_invocation.tokens = self.lift(binding.macro_invocation.tokens, true, &mut lifted)?;
+            lifted.push(binding);
+        }
+        let body = self.lift(body, false, &mut lifted)?;
+
+        Ok((lifted, body))
+    }
+
+    fn lift(
+        &mut self,
+        tokens: TokenStream,
+        in_arguments: bool,
+        lifted: &mut Vec<LetBinding>,
+    ) -> syn::Result<TokenStream> {
+        let tokens: Vec<TokenTree> = tokens.into_iter().collect();
+        let mut output = TokenStream::new();
+        let mut i = 0;
+        while i < tokens.len() {
+            // Macros defined inside the tokens escape their own eager calls, which are lifted when they are defined
+            if let Some(len) = self.nested_definition(&tokens[i..]) {
+                output.extend(tokens[i..i + len].iter().cloned());
+                i += len;
+                continue;
+            }
+
+            let call = match self.builtin_call(&tokens[i..], "eager") {
+                Some((len, call)) => Some((len, TokenStream::new(), call)),
+                None if in_arguments => double_bang_call(&tokens[i..]),
+                None => None,
+            };
+            if let Some((len, path, call)) = call {
+                let mut inner = self.lift(call.stream(), in_arguments, lifted)?;
+                if !path.is_empty() {
+                    let mut args = Group::new(call.delimiter(), inner);
+                    args.set_span(call.span());
+                    inner = quote! { #path ! #args };
+                }
+
+                let name = self.names.ident(&format!("eager{}", self.count));
+                self.count += 1;
+                let binding: LetBinding = syn::parse2(quote! { let $($ #name :tt)* = #inner in })
+                    .map_err(|_| syn::Error::new(call.span(), "expected a macro invocation inside `cps::eager!`"))?;
+                lifted.push(binding);
+
+                output.extend(quote! { $($ #name)* });
+                i += len;
+                continue;
+            }
+
+            match &tokens[i] {
+                TokenTree::Group(g) => {
+                    let mut new_group = Group::new(g.delimiter(), self.lift(g.stream(), in_arguments, lifted)?);
+                    new_group.set_span(g.span());
+                    output.extend([TokenTree::Group(new_group)]);
+                }
+                tt => output.extend([tt.clone()]),
+            }
+            i += 1;
+        }
+
+        Ok(output)
+    }
+
+    /// Checks whether the tokens begin with a call to the builtin `name`, through `cps::`, `::cps::` or the crate
+    /// path given to the attribute, returning the number of tokens in the call and its arguments.
+    fn builtin_call<'t>(&self, tokens: &'t [TokenTree], name: &str) -> Option<(usize, &'t Group)> {
+        let (len, path, call) = macro_call(tokens)?;
+        let mut segments: Vec<TokenTree> = path.into_iter().collect();
+        match segments.pop() {
+            Some(TokenTree::Ident(last)) if last == name => {}
+            _ => return None,
+        }
+        // Drop the `::` before the name
+        segments.truncate(segments.len().checked_sub(2)?);
+        let krate = segments.into_iter().collect::<TokenStream>().to_string();
+        match krate == "cps" || krate == ":: cps" || krate == self.attr.crate_path().to_string() {
+            true => Some((len, call)),
+            false => None,
+        }
+    }
+
+    /// Checks whether the tokens begin with a nested macro definition, either a `macro_rules!` or an eval block,
+    /// returning the number of tokens in the definition.
+    fn nested_definition(&self, tokens: &[TokenTree]) -> Option<usize> {
+        let is_macro_rules = matches!(
+            tokens,
+            [TokenTree::Ident(keyword), TokenTree::Punct(bang), TokenTree::Ident(_), TokenTree::Group(_), ..]
+                if keyword == "macro_rules" && bang.as_char() == '!'
+        );
+        match is_macro_rules {
+            true => Some(4),
+            false => self.builtin_call(tokens, "eval").map(|(len, _)| len),
+        }
+    }
+}
+
+/// Checks whether the tokens begin with a macro call through a path, optionally with a leading `::`, returning the
+/// number of tokens in the call, the path and its arguments.
+fn macro_call(tokens: &[TokenTree]) -> Option<(usize, TokenStream, &Group)> {
+    let is_punct = |i: usize, c: char| matches!(tokens.get(i), Some(TokenTree::Punct(p)) if p.as_char() == c);
+    let is_ident = |i: usize| matches!(tokens.get(i), Some(TokenTree::Ident(_)));
+
+    let mut end = if is_punct(0, ':') && is_punct(1, ':') { 2 } else { 0 };
+    if !is_ident(end) {
+        return None;
+    }
+    end += 1;
+    while is_punct(end, ':') && is_punct(end + 1, ':') && is_ident(end + 2) {
+        end += 3;
+    }
+
+    match tokens.get(end + 1) {
+        Some(TokenTree::Group(call)) if is_punct(end, '!') => {
+            Some((end + 2, tokens[..end].iter().cloned().collect(), call))
+        }
+        _ => None,
+    }
+}
+
+/// Checks whether the tokens begin with a call marked as eager with `name!!(...)`, where the name can be any path
+/// or a metavariable, returning the number of tokens in the call, the path and its arguments.
+fn double_bang_call(tokens: &[TokenTree]) -> Option<(usize, TokenStream, &Group)> {
+    let is_punct = |i: usize, c: char| matches!(tokens.get(i), Some(TokenTree::Punct(p)) if p.as_char() == c);
+    let is_ident = |i: usize| matches!(tokens.get(i), Some(TokenTree::Ident(_)));
+
+    let mut end = 0;
+    if is_punct(end, '$') {
+        end += 1;
+    } else if is_punct(end, ':') && is_punct(end + 1, ':') {
+        end += 2;
+    }
+    if !is_ident(end) {
+        return None;
+    }
+    end += 1;
+    while is_punct(end, ':') && is_punct(end + 1, ':') && is_ident(end + 2) {
+        end += 3;
+    }
+
+    match tokens.get(end + 2) {
+        Some(TokenTree::Group(call)) if is_punct(end, '!') && is_punct(end + 1, '!') => {
+            Some((end + 3, tokens[..end].iter().cloned().collect(), call))
+        }
+        _ => None,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use quote::ToTokens;
+
+    #[test]
+    fn lifted_in_order() {
+        let binding: LetBinding = syn::parse2(quote! { let $x:tt = foo!(cps::eager!(bar!())) in })
+            .expect("test let binding did not parse");
+        let body = quote! { cps::eager!(baz!(::cps::eager!(qux!($x)))) };
+
+        let names = InternalNames::new(&quote! { $x });
+        let (let_bindings, body) = EagerCalls::new(&CPSAttr::default(), &names)
+            .lift_rule(vec![binding], body)
+            .expect("eager calls could not be lifted");
+
+        let rendered: Vec<String> = let_bindings
+            .iter()
+            .map(|binding| {
+                let mac = &binding.macro_invocation;
+                format!("{} = {}", binding.pattern.to_token_stream(), mac.to_token_stream())
+            })
+            .collect();
+        assert_eq!(
+            rendered,
+            vec![
+                "$ ($ _cps_eager0 : tt) * = bar ! ()",
+                "$ x : tt = foo ! ($ ($ _cps_eager0) *)",
+                "$ ($ _cps_eager1 : tt) * = qux ! ($ x)",
+                "$ ($ _cps_eager2 : tt) * = baz ! ($ ($ _cps_eager1) *)",
+            ]
+        );
+        assert_eq!(body.to_string(), "$ ($ _cps_eager2) *");
+    }
+
+    #[test]
+    fn double_bang_only_in_arguments() {
+        let binding: LetBinding = syn::parse2(quote! { let $x:tt = foo!($cont!!(a::bar!!()) baz!()) in })
+            .expect("test let binding did not parse");
+        let body = quote! { qux!!() };
+
+        let names = InternalNames::new(&quote! { $x $cont });
+        let (let_bindings, body) = EagerCalls::new(&CPSAttr::default(), &names)
+            .lift_rule(vec![binding], body.clone())
+            .expect("eager calls could not be lifted");
+
+        let rendered: Vec<String> = let_bindings
+            .iter()
+            .map(|binding| binding.macro_invocation.to_token_stream().to_string())
+            .collect();
+        assert_eq!(
+            rendered,
+            vec![
+                "a :: bar ! ()",
+                "cont ! ($ ($ _cps_eager0) *)",
+                "foo ! ($ ($ _cps_eager1) * baz ! ())",
+            ]
+        );
+        assert!(let_bindings[1].macro_name_indirection.is_some());
+        assert_eq!(body.to_string(), "qux ! ! ()");
+    }
+
+    #[test]
+    fn crate_path_and_nested_definitions() {
+        let attr = CPSAttr::parse(quote! { crate = "facade" }).expect("test attribute did not parse");
+        let body = quote! {
+            facade::eager!(foo!())
+            other::eager!(bar!())
+            macro_rules! inner {
+                () => { cps::eager!(baz!()) };
+            }
+            facade::eval! {
+                let $$x:tt = qux!() in
+                { cps::eager!(qux!($$x)) }
+            }
+        };
+
+        let names = InternalNames::new(&body);
+        let (let_bindings, lifted) = EagerCalls::new(&attr, &names)
+            .lift_rule(Vec::new(), body.clone())
+            .expect("eager calls could not be lifted");
+
+        let rendered: Vec<String> = let_bindings
+            .iter()
+            .map(|binding| binding.macro_invocation.to_token_stream().to_string())
+            .collect();
+        assert_eq!(rendered, vec!["foo ! ()"]);
+        let expected = body.to_string().replace("facade :: eager ! (foo ! ())", "$ ($ _cps_eager0) *");
+        assert_eq!(lifted.to_string(), expected);
+    }
+}
@@ -0,0 +1,123 @@
+use crate::parse_cps_attr::CPSAttr;
+use crate::parse_macro_decl::CPSMacroRule;
+use crate::std_macros::paths::IncludePath;
+use crate::std_macros::read_dir::ReadDirInput;
+use proc_macro2::{Literal, TokenStream};
+use quote::quote;
+use std::collections::BTreeSet;
+use syn::Path;
+
+/// The builtins that read files, which the compiler doesn't know about.
+const INCLUDE_BUILTINS: &[&str] = &["include", "include_str", "include_bytes", "include_bytes_list"];
+
+/// The name of the builtin that a let binding calls, if its macro path is `cps::name` or starts with the crate path
+/// given to the attribute.
+fn builtin_name(attr: &CPSAttr, path: &Path) -> Option<String> {
+    let mut krate = path.clone();
+    let name = krate.segments.pop()?.into_value();
+    krate.segments.pop_punct();
+    if krate.segments.is_empty() {
+        return None;
+    }
+    let krate = quote! { #krate }.to_string();
+    match krate == "cps" || krate == attr.crate_path().to_string() {
+        true => Some(name.ident.to_string()),
+        false => None,
+    }
+}
+
+/// Finds the files read by include builtins, or listed by `read_dir!`, in let bindings whose paths are literals, and
+/// so are known before the macro is invoked.
+fn included_files(attr: &CPSAttr, rules: &[CPSMacroRule]) -> BTreeSet<String> {
+    let mut files = BTreeSet::new();
+    for binding in rules.iter().flat_map(|rule| rule.let_bindings.iter()) {
+        if binding.macro_name_indirection.is_some() {
+            continue;
+        }
+        let name = match builtin_name(attr, &binding.macro_invocation.path) {
+            Some(name) => name,
+            None => continue,
+        };
+
+        // Paths given by metavariables can't be resolved until the macro is invoked, and missing files are
+        // reported by the builtin when it is
+        let tokens = binding.macro_invocation.tokens.clone();
+        let found = if INCLUDE_BUILTINS.contains(&name.as_str()) {
+            syn::parse2::<IncludePath>(tokens)
+                .and_then(|path| path.resolve())
+                .map(|file| vec![file])
+        } else if name == "read_dir" {
+            syn::parse2::<ReadDirInput>(tokens)
+                .and_then(|input| input.files())
+                .map(|listed| listed.into_iter().map(|file| file.path).collect())
+        } else {
+            continue;
+        };
+
+        for file in found.unwrap_or_default() {
+            if let Some(file) = file.to_str().filter(|_| file.is_file()) {
+                files.insert(file.to_owned());
+            }
+        }
+    }
+    files
+}
+
+/// Makes the compiler rebuild the crate that defines a CPS macro when a file that it includes changes, in the same
+/// way as std's include macros do. Proc macros can't register files as dependencies directly, so each file is also
+/// included by std's `include_bytes!` in an unnamed constant.
+pub fn track_included_files(attr: &CPSAttr, rules: &[CPSMacroRule]) -> TokenStream {
+    let files = included_files(attr, rules).into_iter().map(|file| Literal::string(&file));
+    quote! {
+        #(
+            const _: &[u8] = ::core::include_bytes!(#files);
+        )*
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::cps_macro::parse_rules;
+
+    #[test]
+    fn literal_paths_are_tracked() {
+        let rules = parse_rules(quote! {
+            ($path:literal) =>
+            let $a:literal = cps::include_str!(#[manifest_dir] "tests/test_file.txt") in
+            let $b:literal = cps::include_bytes!(#[manifest_dir] "tests/missing.txt") in
+            let $c:literal = cps::include_str!($path) in
+            let $d:literal = other::include_str!(#[manifest_dir] "Cargo.toml") in
+            let $($e:literal)* = cps::read_dir!(#[manifest_dir] "tests/read_dir", "*.sql") in
+            { $a $b $c $d $($e)* }
+        })
+        .expect("test rules did not parse");
+
+        let manifest_dir = std::path::Path::new(env!("CARGO_MANIFEST_DIR"));
+        let expected: Vec<String> = ["tests/read_dir/a_create.sql", "tests/read_dir/b_create.sql", "tests/test_file.txt"]
+            .iter()
+            .map(|file| manifest_dir.join(file).to_str().unwrap().to_owned())
+            .collect();
+        assert_eq!(included_files(&CPSAttr::default(), &rules).into_iter().collect::<Vec<_>>(), expected);
+    }
+
+    #[test]
+    fn crate_path_is_tracked() {
+        let rules = parse_rules(quote! {
+            () =>
+            let $a:literal = facade::include_str!(#[manifest_dir] "tests/test_file.txt") in
+            let $b:literal = cps::include_str!(#[manifest_dir] "Cargo.toml") in
+            { $a $b }
+        })
+        .expect("test rules did not parse");
+        let attr = CPSAttr::parse(quote! { crate = "facade" }).expect("test attribute did not parse");
+
+        let manifest_dir = std::path::Path::new(env!("CARGO_MANIFEST_DIR"));
+        let expected: Vec<String> = ["Cargo.toml", "tests/test_file.txt"]
+            .iter()
+            .map(|file| manifest_dir.join(file).to_str().unwrap().to_owned())
+            .collect();
+        assert_eq!(included_files(&attr, &rules).into_iter().collect::<Vec<_>>(), expected);
+        assert_eq!(included_files(&CPSAttr::default(), &rules).len(), 1);
+    }
+}
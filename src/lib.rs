@@ -1,14 +1,21 @@
 #![deny(missing_docs)]
 #![doc=::std::include_str!("../README.md")]
 
+mod cps_eval;
 mod cps_macro;
+mod cps_module;
 mod cps_proc_macro;
+mod eager_calls;
+mod include_tracking;
+mod macro_matching;
+mod parse_cps_attr;
 mod parse_cps_input;
 mod parse_macro_decl;
 mod std_macros;
+mod validate_macro_decl;
 
 use proc_macro::TokenStream;
-use syn::{parse_macro_input, ItemMacro};
+use syn::{parse_macro_input, Item};
 
 /// Manipulates a macro_rules! definition to add extended syntax to help in creating readable macros.
 ///
@@ -65,6 +72,13 @@ use syn::{parse_macro_input, ItemMacro};
 /// }
 /// ```
 ///
+/// ## Checks
+///
+/// Let bindings are checked when the attribute is applied, rather than when the macro is first invoked. It is an
+/// error for a let binding to use an unknown fragment specifier, to bind a metavariable that is already bound by
+/// the rule pattern or an earlier let binding, or to refer to a metavariable that hasn't yet been bound. Let
+/// bindings that are never used produce a warning, unless their name begins with an underscore.
+///
 /// ## Macro indirection
 ///
 /// The result of a previous let binding can be used as the name of a later let binding:
@@ -100,18 +114,265 @@ use syn::{parse_macro_input, ItemMacro};
 /// }
 /// ```
 ///
+/// Macros can also be given as paths, either captured with a `path` fragment, or referring to a macro exported by
+/// the crate that defines the CPS macro with `$crate::helper!(...)`. Exported macros should use `$crate::` rather than
+/// `crate::`, which refers to whichever crate the macro is invoked from:
+///
+/// ```
+/// # use cps::cps;
+/// mod helpers {
+///     #[cps::cps]
+///     macro_rules! helper {
+///         () => {
+///             BaseCase
+///         };
+///     }
+///     pub(crate) use helper;
+/// }
+///
+/// #[cps]
+/// macro_rules! macro1 {
+///     ($cont:path) =>
+///     let $x:tt = $cont!() in
+///     {
+///         stringify!($x)
+///     };
+/// }
+///
+/// fn main() {
+///     assert_eq!(macro1!(helpers::helper), "BaseCase");
+/// }
+/// ```
+///
+/// Invoking a macro through a `path` fragment requires the `cps` crate to be in scope (see [Crate path](#crate-path)).
+///
+/// ## Nested macro definitions
+///
+/// Rule bodies may define macros of their own. Since the body of a rule is itself transcribed, metavariables and
+/// repetitions that belong to the nested macro must be escaped by writing `$$` in place of `$`:
+///
+/// ```
+/// # use cps::cps;
+/// #[cps]
+/// macro_rules! make_macro {
+///     ($name:ident) =>
+///     let $x:tt = cps::stringify!(BaseCase) in
+///     {
+///         macro_rules! $name {
+///             ($$($$y:expr),*) => {
+///                 [$x $$(, stringify!($$y))*]
+///             };
+///         }
+///     };
+/// }
+///
+/// make_macro!(foo);
+///
+/// fn main() {
+///     assert_eq!(foo!(a, b), ["BaseCase", "a", "b"]);
+/// }
+/// ```
+///
+/// Nested macros may also be marked with `#[cps]`. The metavariables used by generated rules are always chosen to
+/// be distinct from the metavariables in the macro being transformed.
+///
+/// ## Eager calls
+///
+/// A CPS macro call that is only used once can be written inline, by wrapping it in `cps::eager!(...)`, rather than
+/// being given its own let binding. Eager calls can appear anywhere in a rule body or in the arguments of a let
+/// binding, and are evaluated in the order they appear, with calls nested inside other calls evaluated first:
+///
+/// ```
+/// # use cps::cps;
+/// #[cps]
+/// macro_rules! macro1 {
+///     (a) => { CaseA };
+///     (CaseA) => { MatchedCaseA };
+/// }
+///
+/// #[cps]
+/// macro_rules! macro2 {
+///     () => {
+///         concat!(cps::eager!(cps::stringify!(cps::eager!(macro1!(cps::eager!(macro1!(a)))))), "!")
+///     };
+/// }
+///
+/// fn main() {
+///     assert_eq!(macro2!(), "MatchedCaseA!");
+/// }
+/// ```
+///
+/// Calls inside a let binding's arguments are evaluated just before that let binding, and calls in the body are
+/// evaluated after every let binding. Eager calls can't refer to metavariables inside a repetition. Calls through
+/// the [crate path](#crate-path) are recognised as well as `cps::eager!`, and calls inside a `macro_rules!` or
+/// [`eval!`] block defined in the body are left for that definition to evaluate.
+///
+/// Within the arguments of a let binding, a call can also be marked as eager by writing `!!` in place of `!`, so that
+/// let bindings compose like function calls:
+///
+/// ```
+/// # use cps::cps;
+/// #[cps]
+/// macro_rules! macro1 {
+///     (a) => { CaseA };
+///     (CaseA) => { MatchedCaseA };
+/// }
+///
+/// #[cps]
+/// macro_rules! macro2 {
+///     () =>
+///     let $x:tt = cps::stringify!(macro1!!(macro1!!(a))) in // The same as `cps::stringify!(MatchedCaseA)`
+///     {
+///         $x
+///     };
+/// }
+///
+/// fn main() {
+///     assert_eq!(macro2!(), "MatchedCaseA");
+/// }
+/// ```
+///
+/// ## Modules
+///
+/// `#[cps]` can also be applied to an inline module, in which case every `macro_rules!` definition in the module is
+/// transformed. Let bindings that invoke another macro in the same module by name are checked against that macro's
+/// rules, and invocations that could never match any rule are reported when the attribute is applied:
+///
+/// ```
+/// #[cps::cps]
+/// mod my_macros {
+///     macro_rules! macro1 {
+///         (a) => { CaseA };
+///     }
+///
+///     macro_rules! macro2 {
+///         () =>
+///         let $x:tt = macro1!(a) in // `macro1!(b)` would be an error
+///         {
+///             stringify!($x)
+///         };
+///     }
+///
+///     pub fn case_a() -> &'static str {
+///         macro2!()
+///     }
+/// }
+///
+/// fn main() {
+///     assert_eq!(my_macros::case_a(), "CaseA");
+/// }
+/// ```
+///
+/// Macros in the module are given the arguments of the module's attribute, unless they have a `#[cps(...)]` attribute
+/// of their own, which is used instead.
+///
+/// ## Crate path
+///
+/// Generated macros only refer to `::core`, so they can be used in `#![no_std]` crates. Let bindings that call
+/// builtin macros as `cps::...` assume that this crate can be reached as `cps`. Crates that re-export `cps` under
+/// another name can give the path to use instead with `#[cps(crate = "path::to::cps")]`, and exported macros can
+/// refer to a re-export in their own crate with `#[cps(crate = "$crate::cps")]`. As well as the builtins that
+/// are used, the path must reach the hidden `__call_path` macro, which is used by let bindings that call a macro
+/// through a `path` fragment, and [`eval!`], which is used by eval blocks. Re-exporting everything, as with
+/// `pub use cps::*;`, covers these:
+///
+/// ```
+/// mod facade {
+///     pub use cps::stringify;
+/// }
+///
+/// #[cps::cps(crate = "facade")]
+/// macro_rules! foo {
+///     () =>
+///     let $x:tt = cps::stringify!(BaseCase) in // Resolved as `facade::stringify!`
+///     {
+///         $x
+///     };
+/// }
+///
+/// fn main() {
+///     assert_eq!(foo!(), "BaseCase");
+/// }
+/// ```
+///
 /// [tlborm]: https://veykril.github.io/tlborm/decl-macros/patterns/callbacks.html
 #[proc_macro_attribute]
 pub fn cps(attr: TokenStream, item: TokenStream) -> TokenStream {
-    let m = parse_macro_input!(item as ItemMacro);
+    let attr = proc_macro2::TokenStream::from(attr);
+    let res = match parse_macro_input!(item as Item) {
+        Item::Macro(m) => cps_macro::impl_cps(attr, m),
+        Item::Mod(m) => cps_module::impl_cps_mod(attr, m),
+        item => syn::Error::new_spanned(item, "expected a macro_rules! macro definition or a module")
+            .to_compile_error(),
+    };
+
+    TokenStream::from(res)
+}
+
+/// Evaluates let bindings without defining a macro, in any position that a macro can be invoked in.
+///
+/// The block is written like the right hand side of a CPS macro rule: any number of let bindings, followed by a
+/// body that is the result of the block.
+///
+/// ```
+/// #[cps::cps]
+/// macro_rules! names {
+///     (type) => { u32 };
+///     (function) => { make_pair };
+/// }
+///
+/// type Pair = cps::eval! {
+///     let $t:ty = names!(type) in
+///     { ($t, $t) }
+/// };
+///
+/// cps::eval! {
+///     let $name:ident = names!(function) in
+///     {
+///         fn $name() -> Pair { (1, 2) }
+///     }
+/// }
+///
+/// fn main() {
+///     let s = cps::eval! {
+///         let $x:tt = cps::stringify!(a + b) in
+///         let $y:tt = cps::concat!($x, "c") in
+///         { $y }
+///     };
+///     assert_eq!(s, "a + bc");
+///     assert_eq!(make_pair(), (1, 2));
+/// }
+/// ```
+///
+/// As in a CPS macro, let bindings are checked before the block is evaluated. A `#![cps(crate = "...")]` attribute
+/// at the start of the block sets the [crate path](macro@cps#crate-path) used by the block.
+#[proc_macro]
+pub fn eval(item: TokenStream) -> TokenStream {
+    TokenStream::from(cps_eval::impl_eval(proc_macro2::TokenStream::from(item)))
+}
 
-    TokenStream::from(cps_macro::impl_cps(proc_macro2::TokenStream::from(attr), m))
+/// Marks a CPS macro call to be evaluated before the tokens around it. Only has meaning inside a [`macro@cps`] macro
+/// or [`eval!`] block, see [Eager calls](macro@cps#eager-calls).
+#[proc_macro]
+pub fn eager(item: TokenStream) -> TokenStream {
+    let item = proc_macro2::TokenStream::from(item);
+    let message = "`cps::eager!` can only be used inside a `#[cps]` macro or a `cps::eval!` block";
+    TokenStream::from(syn::Error::new_spanned(item, message).to_compile_error())
+}
+
+/// Invokes a macro whose path was captured by a `path` fragment. Used by generated rules, which can't invoke such
+/// paths directly.
+#[doc(hidden)]
+#[proc_macro]
+pub fn __call_path(item: TokenStream) -> TokenStream {
+    TokenStream::from(cps_macro::impl_call_path(proc_macro2::TokenStream::from(item)))
 }
 
 macro_rules! export_std_cps {
-    ($name:ident) => {
+    ($(#[$attr:meta])* $name:ident) => {
 
         #[doc = "Performs the same task as the builtin macro of the same name, but this version can also be used as a let binding in a CPS macro"]
+        $(#[$attr])*
         #[proc_macro]
         pub fn $name(item: TokenStream) -> TokenStream {
             crate::std_macros::$name::$name(item)
@@ -121,5 +382,678 @@ macro_rules! export_std_cps {
 
 export_std_cps!(concat);
 export_std_cps!(stringify);
-export_std_cps!(include);
-export_std_cps!(include_str);
+export_std_cps!(
+    ///
+    /// As with the builtin, relative paths are resolved against the directory of the source file that the path was
+    /// written in. Add `#[manifest_dir]` before the path to resolve it against the directory containing the crate's
+    /// `Cargo.toml` instead, like `cps::include!(#[manifest_dir] "src/generated.rs")`. This is also done when the
+    /// source file isn't known, for example when the path was built by another macro.
+    ///
+    /// When a let binding in a [`macro@cps`] macro includes a file with a literal path, the crate defining the macro
+    /// is rebuilt when the file changes, as it would be for the builtin. Only literal paths in macro definitions are
+    /// tracked: files whose paths are only known when the macro is invoked, such as a path given by a `$path`
+    /// metavariable, and files included by [`eval!`] blocks are read without telling the compiler about them. Crates
+    /// can rebuild when such files change by adding `println!("cargo:rerun-if-changed=path/to/file");` to their
+    /// build script.
+    ///
+    /// Files that can't be lexed are reported with the line and column of the problem. Since proc macros can't
+    /// point to locations in other files, errors in the included tokens point to the path that included them.
+    include
+);
+export_std_cps!(
+    ///
+    /// Paths are resolved in the same way as [`include!`].
+    include_str
+);
+export_std_cps!(
+    ///
+    /// Paths are resolved in the same way as [`include!`].
+    include_bytes
+);
+export_std_cps!(
+    ///
+    /// Variables are read when the macro is expanded. Cargo reruns expansion when the variables it sets, like
+    /// `CARGO_PKG_VERSION`, change, but not necessarily when other variables do. A custom message to show if the
+    /// variable isn't set can be given as a second argument.
+    ///
+    /// ```
+    /// # use cps::cps;
+    /// #[cps]
+    /// macro_rules! crate_banner {
+    ///     () =>
+    ///     let $name:literal = cps::env!("CARGO_PKG_NAME") in
+    ///     let $version:literal = cps::env!("CARGO_PKG_VERSION", "expected to be built by cargo") in
+    ///     let $banner:literal = cps::format!("{} v{}", $name, $version) in
+    ///     {
+    ///         $banner
+    ///     };
+    /// }
+    ///
+    /// assert_eq!(crate_banner!(), concat!("cps v", env!("CARGO_PKG_VERSION")));
+    /// ```
+    env
+);
+export_std_cps!(
+    ///
+    /// The result is `Some("value")` or `None`, using the names from the prelude so that later rules can match on
+    /// them with patterns like `(Some($value:literal))` and `(None)`. See [`env!`] for when variables are read.
+    option_env
+);
+
+macro_rules! export_cps {
+    ($(#[$attr:meta])* $module:ident :: $name:ident) => {
+        $(#[$attr])*
+        #[proc_macro]
+        pub fn $name(item: TokenStream) -> TokenStream {
+            crate::std_macros::$module::$name(item)
+        }
+    };
+}
+
+export_cps!(
+    /// Builds an identifier by concatenating identifiers, strings, characters and integers, for use as a let
+    /// binding in a CPS macro.
+    ///
+    /// The identifier has a call-site span, unless a token to take the span of is given with `#[span(...)]`.
+    /// Keywords can only be created as raw identifiers, by adding `#[raw]`.
+    ///
+    /// ```
+    /// # use cps::cps;
+    /// #[cps]
+    /// macro_rules! getter {
+    ///     ($field:ident) =>
+    ///     let $name:ident = cps::ident!(#[span($field)] get_, $field, "_", 2) in
+    ///     let $keyword:ident = cps::ident!(#[raw] "type") in
+    ///     {
+    ///         fn $name() -> &'static str {
+    ///             stringify!($keyword)
+    ///         }
+    ///     };
+    /// }
+    ///
+    /// getter!(value);
+    ///
+    /// fn main() {
+    ///     assert_eq!(get_value_2(), "r#type");
+    /// }
+    /// ```
+    ident::ident
+);
+
+export_cps!(
+    /// Converts an identifier or string literal to snake_case, for use as a let binding in a CPS macro. Identifiers
+    /// are converted to identifiers and strings to strings.
+    ///
+    /// Words are split at underscores, hyphens, whitespace and changes of case. Leading and trailing underscores are
+    /// kept, so `_privateField` becomes `_private_field`. Identifiers that would become a keyword are made raw.
+    ///
+    /// ```
+    /// # use cps::cps;
+    /// #[cps]
+    /// macro_rules! getter {
+    ///     ($field:ident) =>
+    ///     let $name:ident = cps::to_snake_case!($field) in
+    ///     let $string:literal = cps::to_snake_case!("HTTPServer") in
+    ///     {
+    ///         fn $name() -> &'static str {
+    ///             $string
+    ///         }
+    ///     };
+    /// }
+    ///
+    /// getter!(GetValue);
+    ///
+    /// fn main() {
+    ///     assert_eq!(get_value(), "http_server");
+    /// }
+    /// ```
+    case::to_snake_case
+);
+
+export_cps!(
+    /// Converts an identifier or string literal to UPPER_SNAKE_CASE, for use as a let binding in a CPS macro. See
+    /// [`to_snake_case!`] for how words are split.
+    case::to_upper_snake_case
+);
+
+export_cps!(
+    /// Converts an identifier or string literal to camelCase, for use as a let binding in a CPS macro. See
+    /// [`to_snake_case!`] for how words are split.
+    case::to_camel_case
+);
+
+export_cps!(
+    /// Converts an identifier or string literal to PascalCase, for use as a let binding in a CPS macro. See
+    /// [`to_snake_case!`] for how words are split.
+    case::to_pascal_case
+);
+
+export_cps!(
+    /// Converts a string literal to kebab-case, for use as a let binding in a CPS macro. See [`to_snake_case!`] for
+    /// how words are split. Since kebab-case can't be used in identifiers, only string literals are accepted.
+    case::to_kebab_case
+);
+
+export_cps!(
+    /// Adds integer literals together, for use as a let binding in a CPS macro.
+    ///
+    /// The arithmetic builtins take integer literals, which may be negative and may have a suffix. Literals with
+    /// different suffixes can't be mixed, but unsuffixed literals can be used with any suffix, and the result has the
+    /// suffix of the arguments. Overflowing the type given by the suffix, or dividing by zero, is a compile error.
+    /// Proc macros can't find the pointer width of the target, so `usize` and `isize` are checked as 64 bit integers,
+    /// leaving the compiler to report results that don't fit a narrower target.
+    ///
+    /// ```
+    /// # use cps::cps;
+    /// #[cps]
+    /// macro_rules! array {
+    ///     ($($x:literal),*) =>
+    ///     let $sum:literal = cps::add!($($x),*) in
+    ///     let $double:literal = cps::mul!($sum, 2usize) in
+    ///     {
+    ///         [0u8; $double]
+    ///     };
+    /// }
+    ///
+    /// fn main() {
+    ///     assert_eq!(array!(1, 2, 3).len(), 12);
+    /// }
+    /// ```
+    arithmetic::add
+);
+
+export_cps!(
+    /// Subtracts one integer literal from another, for use as a let binding in a CPS macro. See [`add!`] for how
+    /// literals are handled.
+    ///
+    /// For example `cps::sub!(5, 7)` results in `-2`.
+    arithmetic::sub
+);
+
+export_cps!(
+    /// Multiplies integer literals together, for use as a let binding in a CPS macro. See [`add!`] for how literals are
+    /// handled.
+    ///
+    /// For example `cps::mul!(2, 3, 4)` results in `24`.
+    arithmetic::mul
+);
+
+export_cps!(
+    /// Divides one integer literal by another, rounding towards zero, for use as a let binding in a CPS macro. See
+    /// [`add!`] for how literals are handled.
+    ///
+    /// For example `cps::div!(7, 2)` results in `3`.
+    arithmetic::div
+);
+
+export_cps!(
+    /// Finds the remainder of dividing one integer literal by another, for use as a let binding in a CPS macro. See
+    /// [`add!`] for how literals are handled.
+    ///
+    /// For example `cps::rem!(-7, 2)` results in `-1`.
+    arithmetic::rem
+);
+
+export_cps!(
+    /// Finds the smallest of some integer literals, for use as a let binding in a CPS macro. See [`add!`] for how
+    /// literals are handled.
+    ///
+    /// For example `cps::min!(3, -1, 2)` results in `-1`.
+    arithmetic::min
+);
+
+export_cps!(
+    /// Finds the largest of some integer literals, for use as a let binding in a CPS macro. See [`add!`] for how
+    /// literals are handled.
+    ///
+    /// For example `cps::max!(3, -1, 2)` results in `3`.
+    arithmetic::max
+);
+
+export_cps!(
+    /// Checks whether one literal is less than another, resulting in `true` or `false`, for use as a let binding in
+    /// a CPS macro. Integer, float, string and character literals can be compared with literals of the same kind, and
+    /// integers follow the rules of [`add!`].
+    arithmetic::lt
+);
+
+export_cps!(
+    /// Checks whether one literal is less than or equal to another, resulting in `true` or `false`, for use as a let
+    /// binding in a CPS macro. See [`lt!`] for which literals can be compared.
+    arithmetic::le
+);
+
+export_cps!(
+    /// Checks whether one literal is greater than another, resulting in `true` or `false`, for use as a let binding
+    /// in a CPS macro. See [`lt!`] for which literals can be compared.
+    arithmetic::gt
+);
+
+export_cps!(
+    /// Checks whether one literal is greater than or equal to another, resulting in `true` or `false`, for use as a
+    /// let binding in a CPS macro. See [`lt!`] for which literals can be compared.
+    arithmetic::ge
+);
+
+export_cps!(
+    /// Evaluates a constant expression, resulting in a literal, for use as a let binding in a CPS macro.
+    ///
+    /// Expressions are made of integer, `bool`, `char` and string literals, combined with parentheses, arithmetic,
+    /// bitwise, logical and comparison operators, and `as` casts to integer types, `char` and `bool`. Integer types
+    /// follow the rules of [`add!`], and overflow is a compile error. Unsuffixed integers take the type of the integer
+    /// they are combined with, or can have any value of an `i128` or `u128` otherwise, so shifting them left never
+    /// discards bits and `!` negates them as signed integers. Unsuffixed integers cast to `char` are `u8`s, like in
+    /// Rust. Strings and chars can only be compared.
+    ///
+    /// ```
+    /// # use cps::cps;
+    /// #[cps]
+    /// macro_rules! offset {
+    ///     ($n:literal) =>
+    ///     let $offset:literal = cps::eval_const!(1 + 2 * ($n - 3)) in
+    ///     let $is_large:tt = cps::eval_const!($offset > 8 || "a" == "b") in
+    ///     {
+    ///         ($offset, $is_large)
+    ///     };
+    /// }
+    ///
+    /// fn main() {
+    ///     assert_eq!(offset!(7usize), (9usize, true));
+    /// }
+    /// ```
+    eval_const::eval_const
+);
+
+export_cps!(
+    /// Counts the token trees in its input, resulting in an unsuffixed integer literal, for use as a let binding in a
+    /// CPS macro. Groups and lifetimes count as a single token tree, as do fragments such as `$e:expr` that were
+    /// passed through from another macro.
+    ///
+    /// ```
+    /// # use cps::cps;
+    /// #[cps]
+    /// macro_rules! array_of {
+    ///     ($($x:tt)*) =>
+    ///     let $len:literal = cps::count_tts!($($x)*) in
+    ///     {
+    ///         {
+    ///             let array: [&str; $len] = [$(stringify!($x)),*];
+    ///             array
+    ///         }
+    ///     };
+    /// }
+    ///
+    /// fn main() {
+    ///     assert_eq!(array_of!(a b (c d)), ["a", "b", "(c d)"]);
+    /// }
+    /// ```
+    count::count_tts
+);
+
+export_cps!(
+    /// Counts the comma-separated items in its input, resulting in an unsuffixed integer literal, for use as a let
+    /// binding in a CPS macro. A trailing comma is allowed, and commas inside groups don't separate items.
+    count::count_items
+);
+
+export_cps!(
+    /// Counts the characters in a string literal, resulting in an unsuffixed integer literal, for use as a let
+    /// binding in a CPS macro.
+    count::str_len
+);
+
+export_cps!(
+    /// Counts the bytes in the UTF-8 encoding of a string literal, resulting in an unsuffixed integer literal, for
+    /// use as a let binding in a CPS macro.
+    count::str_byte_len
+);
+
+export_cps!(
+    /// Checks whether two comma-separated token streams are made of the same tokens, ignoring spans, resulting in
+    /// `true` or `false`, for use as a let binding in a CPS macro. Wrap token streams that contain commas or may be
+    /// empty in parentheses.
+    ///
+    /// ```
+    /// # use cps::cps;
+    /// #[cps]
+    /// macro_rules! describe {
+    ///     (@choose true) => { "unit" };
+    ///     (@choose false) => { "other" };
+    ///
+    ///     ($t:ty) =>
+    ///     let $is_unit:tt = cps::eq!($t, ()) in
+    ///     let $description:literal = describe!(@choose $is_unit) in
+    ///     {
+    ///         $description
+    ///     };
+    /// }
+    ///
+    /// fn main() {
+    ///     assert_eq!(describe!(()), "unit");
+    ///     assert_eq!(describe!(u32), "other");
+    /// }
+    /// ```
+    logic::eq
+);
+
+export_cps!(
+    /// Checks whether two comma-separated token streams differ, ignoring spans, resulting in `true` or `false`, for
+    /// use as a let binding in a CPS macro. See [`eq!`] for how token streams are compared.
+    logic::ne
+);
+
+export_cps!(
+    /// Checks whether two identifiers are the same, treating raw identifiers like `r#type` as equal to `type`,
+    /// resulting in `true` or `false`, for use as a let binding in a CPS macro.
+    logic::ident_eq
+);
+
+export_cps!(
+    /// Negates a `true` or `false` token, for use as a let binding in a CPS macro.
+    logic::not
+);
+
+export_cps!(
+    /// Results in `true` if every comma-separated `true` or `false` token is `true`, for use as a let binding in a
+    /// CPS macro. With no arguments the result is `true`.
+    logic::and
+);
+
+export_cps!(
+    /// Results in `true` if any comma-separated `true` or `false` token is `true`, for use as a let binding in a CPS
+    /// macro. With no arguments the result is `false`.
+    logic::or
+);
+
+export_cps!(
+    /// Checks whether the input is a single identifier that isn't a keyword, resulting in `true` or `false`, for use
+    /// as a let binding in a CPS macro. Raw identifiers like `r#fn` are identifiers.
+    classify::is_ident
+);
+
+export_cps!(
+    /// Checks whether the input is a single keyword, like `fn` or `self`, resulting in `true` or `false`, for use as
+    /// a let binding in a CPS macro.
+    classify::is_keyword
+);
+
+export_cps!(
+    /// Checks whether the input is a literal, resulting in `true` or `false`, for use as a let binding in a CPS
+    /// macro. This accepts the same tokens as a `$x:literal` fragment, including negative numbers, `true` and `false`.
+    classify::is_literal
+);
+
+export_cps!(
+    /// Finds the kind of a literal, resulting in one of the identifiers `bool`, `int`, `float`, `char`, `str`, `byte`
+    /// or `byte_str`, for use as a let binding in a CPS macro. Anything that isn't a literal is a compile error; see
+    /// [`is_literal!`] for which tokens are literals.
+    ///
+    /// ```
+    /// # use cps::cps;
+    /// #[cps]
+    /// macro_rules! default_of {
+    ///     (@default int) => { 0 };
+    ///     (@default str) => { "" };
+    ///     (@default bool) => { false };
+    ///
+    ///     ($x:literal) =>
+    ///     let $kind:ident = cps::literal_kind!($x) in
+    ///     let $default:literal = default_of!(@default $kind) in
+    ///     {
+    ///         $default
+    ///     };
+    /// }
+    ///
+    /// fn main() {
+    ///     assert_eq!(default_of!(-12), 0);
+    ///     assert_eq!(default_of!("text"), "");
+    ///     assert_eq!(default_of!(true), false);
+    /// }
+    /// ```
+    classify::literal_kind
+);
+
+export_cps!(
+    /// Checks whether the input is a single lifetime, like `'a`, resulting in `true` or `false`, for use as a let
+    /// binding in a CPS macro.
+    classify::is_lifetime
+);
+
+export_cps!(
+    /// Checks whether the input is a single group in parentheses, brackets or braces, resulting in `true` or `false`,
+    /// for use as a let binding in a CPS macro.
+    classify::is_group
+);
+
+export_cps!(
+    /// Finds the delimiter of a group, resulting in one of the identifiers `paren`, `bracket` or `brace`, for use as
+    /// a let binding in a CPS macro. Anything that isn't a single group is a compile error.
+    classify::group_delimiter
+);
+
+export_cps!(
+    /// Checks whether the input has no tokens, resulting in `true` or `false`, for use as a let binding in a CPS
+    /// macro.
+    classify::is_empty
+);
+
+export_cps!(
+    /// Checks whether the input parses as a type, resulting in `true` or `false`, for use as a let binding in a CPS
+    /// macro.
+    classify::is_type
+);
+
+export_cps!(
+    /// Checks whether the input parses as an expression, resulting in `true` or `false`, for use as a let binding in
+    /// a CPS macro.
+    classify::is_expr
+);
+
+export_cps!(
+    /// Checks whether the input parses as a path, like `std::vec::Vec<u8>`, resulting in `true` or `false`, for use
+    /// as a let binding in a CPS macro.
+    classify::is_path
+);
+
+export_cps!(
+    /// Checks whether the input parses as a single item, like a function or struct, resulting in `true` or `false`,
+    /// for use as a let binding in a CPS macro.
+    classify::is_item
+);
+
+export_cps!(
+    /// Parses the contents of a string literal as tokens, for use as a let binding in a CPS macro. This is the inverse
+    /// of [`stringify!`], and accepts both normal and raw strings.
+    ///
+    /// The tokens are given the span of the string literal, so that errors in them point to the string. Use
+    /// `#[span(token)]` before the literal to give them the span of another token instead. Strings that can't be
+    /// parsed as tokens, for example because they have unbalanced delimiters, are a compile error.
+    ///
+    /// ```
+    /// # use cps::cps;
+    /// #[cps]
+    /// macro_rules! make_getter {
+    ///     ($name:ident) =>
+    ///     let $name_str:literal = cps::stringify!($name) in
+    ///     let $fn_name:literal = cps::concat!("get_", $name_str) in
+    ///     let $fn_ident:ident = cps::unstringify!(#[span($name)] $fn_name) in
+    ///     {
+    ///         fn $fn_ident() -> &'static str {
+    ///             stringify!($name)
+    ///         }
+    ///     };
+    /// }
+    ///
+    /// make_getter!(value);
+    ///
+    /// fn main() {
+    ///     assert_eq!(get_value(), "value");
+    /// }
+    /// ```
+    unstringify::unstringify
+);
+
+export_cps!(
+    /// Replaces every occurrence of one string in a string literal with another, for use as a let binding in a CPS
+    /// macro. String arguments can be normal or raw strings, and escapes are decoded before the strings are
+    /// processed.
+    ///
+    /// ```
+    /// # use cps::cps;
+    /// #[cps]
+    /// macro_rules! module_path_to_file {
+    ///     ($path:literal) =>
+    ///     let $stem:literal = cps::str_replace!($path, "::", "/") in
+    ///     let $file:literal = cps::concat!($stem, ".rs") in
+    ///     {
+    ///         $file
+    ///     };
+    /// }
+    ///
+    /// fn main() {
+    ///     assert_eq!(module_path_to_file!("std_macros::strings"), "std_macros/strings.rs");
+    /// }
+    /// ```
+    strings::str_replace
+);
+
+export_cps!(
+    /// Splits a string literal on a separator, resulting in a comma-separated list of string literals, for use as a
+    /// let binding in a CPS macro. Bind the result with a repetition, like `let $($part:literal),* = ...`.
+    strings::str_split
+);
+
+export_cps!(
+    /// Removes leading and trailing whitespace from a string literal, for use as a let binding in a CPS macro.
+    strings::str_trim
+);
+
+export_cps!(
+    /// Converts a string literal to uppercase, for use as a let binding in a CPS macro.
+    strings::str_to_upper
+);
+
+export_cps!(
+    /// Converts a string literal to lowercase, for use as a let binding in a CPS macro.
+    strings::str_to_lower
+);
+
+export_cps!(
+    /// Checks whether a string literal starts with another, resulting in `true` or `false`, for use as a let binding
+    /// in a CPS macro.
+    strings::str_starts_with
+);
+
+export_cps!(
+    /// Checks whether a string literal ends with another, resulting in `true` or `false`, for use as a let binding in
+    /// a CPS macro.
+    strings::str_ends_with
+);
+
+export_cps!(
+    /// Checks whether a string literal contains another, resulting in `true` or `false`, for use as a let binding in
+    /// a CPS macro.
+    strings::str_contains
+);
+
+export_cps!(
+    /// Takes the characters of a string literal in a range, like `str_substr!("hello", 1, 3)` for `"el"`, for use as
+    /// a let binding in a CPS macro. The range is counted in chars rather than bytes, and the end can be left out to
+    /// take the rest of the string. Ranges that go past the end of the string are a compile error.
+    strings::str_substr
+);
+
+export_cps!(
+    /// Repeats a string literal a number of times, like `str_repeat!("ab", 3)` for `"ababab"`, for use as a let
+    /// binding in a CPS macro. Strings longer than 16 MiB are a compile error.
+    strings::str_repeat
+);
+
+export_cps!(
+    /// Formats literals and identifiers into a string literal, like [`std::format!`], for use as a let binding in a
+    /// CPS macro.
+    ///
+    /// Arguments can be integer, float, string, character and `true` or `false` literals, or identifiers, which are
+    /// formatted as their text. Placeholders can refer to arguments implicitly, by position like `{0}`, or by name
+    /// like `{name}` for an argument given as `name = value`. Format specs support fill and alignment, `+`, `#` and
+    /// `0` flags, widths and precisions (including from arguments with `1$`, `name$` and `.*`), and the `?`, `x`,
+    /// `X`, `b`, `o`, `e` and `E` formats. Integers are formatted as the type given by their suffix, or `i32` if they
+    /// have none. Unused arguments and unknown names are compile errors, as they are in `std`.
+    ///
+    /// ```
+    /// # use cps::cps;
+    /// #[cps]
+    /// macro_rules! register {
+    ///     ($name:ident, $addr:literal) =>
+    ///     let $doc:literal = cps::format!("The `{name}` register, at address {:#06x}.", $addr, name = $name) in
+    ///     {
+    ///         #[doc = $doc]
+    ///         pub const $name: u16 = $addr;
+    ///     };
+    /// }
+    ///
+    /// register!(STATUS, 0x3f);
+    ///
+    /// fn main() {
+    ///     assert_eq!(STATUS, 63);
+    /// }
+    /// ```
+    format::format
+);
+
+export_cps!(
+    /// Includes a file as a comma-separated list of `u8` literals, like `104u8, 105u8`, for use as a let binding in a
+    /// CPS macro. Bind the result with a repetition, like `let $($byte:literal),* = ...`, to iterate over or count
+    /// the bytes. Paths are found in the same way as [`include_bytes!`].
+    ///
+    /// ```
+    /// # use cps::cps;
+    /// #[cps]
+    /// macro_rules! checksum {
+    ///     ($path:literal) =>
+    ///     let $($byte:literal),* = cps::include_bytes_list!(#[manifest_dir] $path) in
+    ///     let $count:literal = cps::count_items!($($byte),*) in
+    ///     {
+    ///         ($count, 0u8 $(^ $byte)*)
+    ///     };
+    /// }
+    ///
+    /// assert_eq!(checksum!("tests/test_file.txt"), (52, 0x31));
+    /// ```
+    include_bytes::include_bytes_list
+);
+
+export_cps!(
+    /// Lists the files in a directory, resulting in their names as string literals sorted in order, for use as a let
+    /// binding in a CPS macro. Bind the result with a repetition, like `let $($file:literal)* = ...`.
+    ///
+    /// The directory is found in the same way as the path given to [`include!`]. A pattern can be given as a second
+    /// argument to only list files whose names match it, where `*` matches any number of characters and `?` matches
+    /// one, like `"*.sql"`. Directories aren't listed, unless `#[recursive]` is given to list the files inside them
+    /// too, with names like `"nested/file.sql"`. Symlinked directories are followed, unless they lead back to a
+    /// directory that is already being listed. Give `#[paths]` to result in the full paths of the files instead of
+    /// their names, which can be passed to the include builtins.
+    ///
+    /// When a let binding in a [`macro@cps`] macro lists a directory with a literal path, the crate defining the
+    /// macro is rebuilt when the listed files change. Compilers can't be told to watch a directory from a macro,
+    /// so to also rebuild when files are added or removed, add `println!("cargo:rerun-if-changed=path/to/dir");` to
+    /// the crate's build script.
+    ///
+    /// ```
+    /// # use cps::cps;
+    /// #[cps]
+    /// macro_rules! migrations {
+    ///     () =>
+    ///     let $($file:literal)* = cps::read_dir!(#[manifest_dir] "tests/read_dir", "*.sql") in
+    ///     let $($path:literal)* = cps::read_dir!(#[manifest_dir] #[paths] "tests/read_dir", "*.sql") in
+    ///     {
+    ///         [$(($file, include_str!($path))),*]
+    ///     };
+    /// }
+    ///
+    /// let migrations = migrations!();
+    /// assert_eq!(migrations[0], ("a_create.sql", "CREATE TABLE a (id INTEGER);\n"));
+    /// assert_eq!(migrations.len(), 2);
+    /// ```
+    read_dir::read_dir
+);
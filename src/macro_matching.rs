@@ -0,0 +1,449 @@
+use std::collections::{BTreeMap, BTreeSet};
+
+use crate::parse_macro_decl::{MacroMatch, MacroMatcher, MacroRepOp, MacroRepetition};
+use crate::validate_macro_decl::collect_bindings;
+use proc_macro2::{Delimiter, Group, Ident, Span, TokenStream, TokenTree};
+use syn::parse::{ParseStream, Parser};
+
+/// A token in a macro invocation that is being checked against macro rules before expansion. Metavariables in the
+/// invocation can't be known until the macro is expanded, so are `Unknown` and may match anything.
+#[derive(Clone)]
+pub enum InvocationToken {
+    Token(TokenTree),
+    Group(Delimiter, Vec<InvocationToken>),
+    Unknown,
+}
+
+impl InvocationToken {
+    /// Converts the arguments of an invocation. Returns `None` if the arguments contain a repetition, since the
+    /// number of tokens it expands to can't be known.
+    pub fn from_tokens(tokens: TokenStream) -> Option<Vec<Self>> {
+        let mut res = Vec::new();
+        let mut tokens = tokens.into_iter().peekable();
+        while let Some(tt) = tokens.next() {
+            match tt {
+                TokenTree::Punct(p) if p.as_char() == '$' => match tokens.peek() {
+                    Some(TokenTree::Ident(_)) => {
+                        tokens.next();
+                        res.push(Self::Unknown);
+                    }
+                    Some(TokenTree::Group(_)) => return None,
+                    _ => res.push(Self::Token(TokenTree::Punct(p))),
+                },
+                TokenTree::Group(g) => res.push(Self::Group(g.delimiter(), Self::from_tokens(g.stream())?)),
+                tt => res.push(Self::Token(tt)),
+            }
+        }
+        Some(res)
+    }
+
+    fn to_token_stream(tokens: &[Self]) -> TokenStream {
+        tokens
+            .iter()
+            .map(|token| match token {
+                Self::Token(tt) => tt.clone(),
+                Self::Group(delimiter, inner) => {
+                    TokenTree::Group(Group::new(*delimiter, Self::to_token_stream(inner)))
+                }
+                Self::Unknown => TokenTree::Ident(Ident::new("__cps_unknown", Span::call_site())),
+            })
+            .collect()
+    }
+}
+
+/// Checks whether some expansion of an invocation could be matched by the given pattern.
+pub fn could_match(pattern: &MacroMatcher, tokens: &[InvocationToken]) -> bool {
+    match_sequence(&pattern.matches, tokens, 0).contains(&tokens.len())
+}
+
+/// Finds every position that matching the given sequence of matches could finish at, when starting at `start`.
+fn match_sequence(matches: &[MacroMatch], tokens: &[InvocationToken], start: usize) -> BTreeSet<usize> {
+    let mut positions = BTreeSet::from([start]);
+    for m in matches {
+        positions = positions
+            .into_iter()
+            .flat_map(|position| match_single(m, tokens, position))
+            .collect();
+        if positions.is_empty() {
+            break;
+        }
+    }
+    positions
+}
+
+fn match_single(m: &MacroMatch, tokens: &[InvocationToken], position: usize) -> BTreeSet<usize> {
+    let token = match tokens.get(position) {
+        Some(token) => token,
+        None => {
+            return match m {
+                MacroMatch::Repetition(r) if !matches!(r.rep_op, MacroRepOp::Plus(_)) => BTreeSet::from([position]),
+                MacroMatch::Identifier(i) if i.macro_frag_spec == "vis" => BTreeSet::from([position]),
+                _ => BTreeSet::new(),
+            }
+        }
+    };
+    let single = BTreeSet::from([position + 1]);
+
+    match (m, token) {
+        (MacroMatch::Repetition(r), _) => {
+            let mut ends = BTreeSet::new();
+            if !matches!(r.rep_op, MacroRepOp::Plus(_)) {
+                ends.insert(position);
+            }
+
+            let mut frontier = match_sequence(&r.sub_matches.matches, tokens, position);
+            while !frontier.is_empty() {
+                let new_ends: Vec<_> = frontier.difference(&ends).copied().collect();
+                ends.extend(new_ends.iter().copied());
+                if matches!(r.rep_op, MacroRepOp::Optional(_)) {
+                    break;
+                }
+
+                frontier = BTreeSet::new();
+                for end in new_ends {
+                    let next = match &r.rep_sep {
+                        None => end,
+                        Some(sep) => match tokens.get(end) {
+                            Some(InvocationToken::Unknown) => end + 1,
+                            Some(InvocationToken::Token(tt)) if tt.to_string() == sep.token.to_string() => end + 1,
+                            _ => continue,
+                        },
+                    };
+                    frontier.extend(
+                        match_sequence(&r.sub_matches.matches, tokens, next)
+                            .into_iter()
+                            .filter(|e| *e > end),
+                    );
+                }
+            }
+            ends
+        }
+        (_, InvocationToken::Unknown) => single,
+        (MacroMatch::Ident(expected), InvocationToken::Token(TokenTree::Ident(i))) if expected == i => single,
+        (MacroMatch::Punct(expected), InvocationToken::Token(TokenTree::Punct(p)))
+            if expected.as_char() == p.as_char() =>
+        {
+            single
+        }
+        (MacroMatch::Literal(expected), InvocationToken::Token(TokenTree::Literal(l)))
+            if expected.to_string() == l.to_string() =>
+        {
+            single
+        }
+        (MacroMatch::Group(delimiter, inner), InvocationToken::Group(d, inner_tokens)) if delimiter == d => {
+            if could_match(inner, inner_tokens) {
+                single
+            } else {
+                BTreeSet::new()
+            }
+        }
+        (MacroMatch::Identifier(i), _) => match_fragment(&i.macro_frag_spec.to_string(), tokens, position),
+        _ => BTreeSet::new(),
+    }
+}
+
+fn match_fragment(frag_spec: &str, tokens: &[InvocationToken], position: usize) -> BTreeSet<usize> {
+    let token = &tokens[position];
+    let single = BTreeSet::from([position + 1]);
+    let is_punct = |i: usize, c: char| {
+        matches!(tokens.get(i), Some(InvocationToken::Token(TokenTree::Punct(p))) if p.as_char() == c)
+    };
+    let is_lifetime = is_punct(position, '\'')
+        && matches!(tokens.get(position + 1), Some(InvocationToken::Token(TokenTree::Ident(_))));
+
+    match frag_spec {
+        // A lifetime is two tokens, but a single token tree
+        "tt" if is_lifetime => BTreeSet::from([position + 2]),
+        "tt" => single,
+        "ident" => match token {
+            InvocationToken::Token(TokenTree::Ident(i)) if i != "_" => single,
+            _ => BTreeSet::new(),
+        },
+        "lifetime" if is_lifetime => BTreeSet::from([position + 2]),
+        "lifetime" => BTreeSet::new(),
+        // Statements may or may not end with a semicolon, so allow any non-empty sequence of tokens
+        "stmt" => (position + 1..=tokens.len()).collect(),
+        "literal" => match (token, tokens.get(position + 1)) {
+            (InvocationToken::Token(TokenTree::Literal(_)), _) => single,
+            (InvocationToken::Token(TokenTree::Ident(i)), _) if i == "true" || i == "false" => single,
+            (_, Some(InvocationToken::Token(TokenTree::Literal(_)))) if is_punct(position, '-') => {
+                BTreeSet::from([position + 2])
+            }
+            _ => BTreeSet::new(),
+        },
+        _ => {
+            let mut ends = BTreeSet::new();
+            if let Some(len) = parsed_fragment_len(frag_spec, InvocationToken::to_token_stream(&tokens[position..])) {
+                ends.insert(position + len);
+            }
+            // A forwarded fragment is opaque and matches as a single token
+            if matches!(token, InvocationToken::Unknown) {
+                ends.insert(position + 1);
+            }
+            ends
+        }
+    }
+}
+
+/// Finds the number of tokens at the start of a token stream that a fragment parsed by the Rust parser consumes.
+/// These fragments consume as many tokens as they can.
+fn parsed_fragment_len(frag_spec: &str, tokens: TokenStream) -> Option<usize> {
+    let total = tokens.clone().into_iter().count();
+    let rest = (|input: ParseStream| {
+        parse_fragment(frag_spec, input)?;
+        Ok(input.parse::<TokenStream>()?.into_iter().count())
+    })
+    .parse2(tokens)
+    .ok()?;
+    Some(total - rest)
+}
+
+fn parse_fragment(frag_spec: &str, input: ParseStream) -> syn::Result<()> {
+    match frag_spec {
+        "block" => input.parse::<syn::Block>().map(|_| ()),
+        "expr" | "expr_2021" => input.parse::<syn::Expr>().map(|_| ()),
+        "item" => input.parse::<syn::Item>().map(|_| ()),
+        "meta" => input.parse::<syn::Meta>().map(|_| ()),
+        "pat" => syn::Pat::parse_multi_with_leading_vert(input).map(|_| ()),
+        "pat_param" => syn::Pat::parse_single(input).map(|_| ()),
+        "path" => input.parse::<syn::Path>().map(|_| ()),
+        "stmt" => input.parse::<syn::Stmt>().map(|_| ()),
+        "ty" => input.parse::<syn::Type>().map(|_| ()),
+        "vis" => input.parse::<syn::Visibility>().map(|_| ()),
+        _ => Err(input.error("unknown fragment specifier")),
+    }
+}
+
+/// The value bound to a metavariable by matching a pattern.
+#[derive(Clone)]
+pub enum Binding {
+    Fragment(TokenStream),
+    Repetition(Vec<Binding>),
+}
+
+pub type Bindings = BTreeMap<String, Binding>;
+
+/// Matches a pattern against a sequence of tokens, returning the metavariables bound by the first way of matching
+/// every token, if there is one.
+pub fn match_bindings(pattern: &MacroMatcher, tokens: TokenStream) -> Option<Bindings> {
+    let tokens: Vec<TokenTree> = tokens.into_iter().collect();
+    bind_sequence(&pattern.matches, &tokens, 0)
+        .into_iter()
+        .find(|(end, _)| *end == tokens.len())
+        .map(|(_, bindings)| bindings)
+}
+
+/// Finds every position that matching the given sequence of matches could finish at when starting at `start`,
+/// along with the metavariables bound on the way.
+fn bind_sequence(matches: &[MacroMatch], tokens: &[TokenTree], start: usize) -> Vec<(usize, Bindings)> {
+    let mut states = vec![(start, Bindings::new())];
+    for m in matches {
+        states = states
+            .into_iter()
+            .flat_map(|(position, bindings)| {
+                bind_single(m, tokens, position)
+                    .into_iter()
+                    .map(move |(end, new_bindings)| {
+                        let mut bindings = bindings.clone();
+                        bindings.extend(new_bindings);
+                        (end, bindings)
+                    })
+            })
+            .collect();
+        if states.is_empty() {
+            break;
+        }
+    }
+    states
+}
+
+fn bind_single(m: &MacroMatch, tokens: &[TokenTree], position: usize) -> Vec<(usize, Bindings)> {
+    let single = |bindings: Bindings| vec![(position + 1, bindings)];
+
+    match (m, tokens.get(position)) {
+        (MacroMatch::Repetition(r), _) => bind_repetition(r, tokens, position),
+        (MacroMatch::Identifier(i), _) => {
+            let frag_spec = i.macro_frag_spec.to_string();
+            bind_fragment(&frag_spec, tokens, position)
+                .into_iter()
+                .map(|(end, fragment)| (end, Bindings::from([(i.identifier.to_string(), Binding::Fragment(fragment))])))
+                .collect()
+        }
+        (MacroMatch::Ident(expected), Some(TokenTree::Ident(i))) if expected == i => single(Bindings::new()),
+        (MacroMatch::Punct(expected), Some(TokenTree::Punct(p))) if expected.as_char() == p.as_char() => {
+            single(Bindings::new())
+        }
+        (MacroMatch::Literal(expected), Some(TokenTree::Literal(l))) if expected.to_string() == l.to_string() => {
+            single(Bindings::new())
+        }
+        (MacroMatch::Group(delimiter, inner), Some(TokenTree::Group(g))) if *delimiter == g.delimiter() => {
+            match match_bindings(inner, g.stream()) {
+                Some(bindings) => single(bindings),
+                None => Vec::new(),
+            }
+        }
+        _ => Vec::new(),
+    }
+}
+
+fn bind_repetition(r: &MacroRepetition, tokens: &[TokenTree], position: usize) -> Vec<(usize, Bindings)> {
+    // Each state is the position after some number of iterations, along with the bindings of every iteration
+    let mut ends = Vec::new();
+    if !matches!(r.rep_op, MacroRepOp::Plus(_)) {
+        ends.push((position, Vec::new()));
+    }
+
+    let mut frontier = vec![(position, Vec::<Bindings>::new())];
+    while !frontier.is_empty() {
+        let mut next_frontier = Vec::new();
+        for (end, iterations) in frontier {
+            let start = match (&r.rep_sep, iterations.is_empty()) {
+                (Some(sep), false) => match tokens.get(end) {
+                    Some(tt) if tt.to_string() == sep.token.to_string() => end + 1,
+                    _ => continue,
+                },
+                _ => end,
+            };
+            // Iterations must consume something, otherwise the repetition would never finish
+            for (new_end, bindings) in bind_sequence(&r.sub_matches.matches, tokens, start) {
+                if new_end > end {
+                    let mut iterations = iterations.clone();
+                    iterations.push(bindings);
+                    next_frontier.push((new_end, iterations));
+                }
+            }
+        }
+
+        ends.extend(next_frontier.iter().cloned());
+        if matches!(r.rep_op, MacroRepOp::Optional(_)) {
+            break;
+        }
+        frontier = next_frontier;
+    }
+
+    let mut names = Vec::new();
+    collect_bindings(&r.sub_matches, &mut names);
+    ends.into_iter()
+        .map(|(end, iterations)| {
+            let bindings = names
+                .iter()
+                .map(|(name, _)| {
+                    let values = iterations
+                        .iter()
+                        .filter_map(|bindings| bindings.get(&name.to_string()).cloned())
+                        .collect();
+                    (name.to_string(), Binding::Repetition(values))
+                })
+                .collect();
+            (end, bindings)
+        })
+        .collect()
+}
+
+/// Finds the ways that a fragment can match the tokens at `position`, along with the tokens it binds. Fragments
+/// parsed by the Rust parser are bound in an invisible group, like they are by `macro_rules!`.
+fn bind_fragment(frag_spec: &str, tokens: &[TokenTree], position: usize) -> Vec<(usize, TokenStream)> {
+    let bound = |end: usize| tokens[position..end].iter().cloned().collect::<TokenStream>();
+    let is_punct = |i: usize, c: char| matches!(tokens.get(i), Some(TokenTree::Punct(p)) if p.as_char() == c);
+
+    let end = match (frag_spec, tokens.get(position), tokens.get(position + 1)) {
+        ("tt", Some(_), _) => position + 1,
+        ("ident", Some(TokenTree::Ident(i)), _) if i != "_" => position + 1,
+        ("lifetime", _, Some(TokenTree::Ident(_))) if is_punct(position, '\'') => position + 2,
+        ("literal", Some(TokenTree::Literal(_)), _) => position + 1,
+        ("literal", Some(TokenTree::Ident(i)), _) if i == "true" || i == "false" => position + 1,
+        ("literal", _, Some(TokenTree::Literal(_))) if is_punct(position, '-') => position + 2,
+        ("tt" | "ident" | "lifetime" | "literal", _, _) => return Vec::new(),
+        _ => {
+            let remaining = tokens[position..].iter().cloned().collect();
+            let len = match parsed_fragment_len(frag_spec, remaining) {
+                Some(len) => len,
+                None => return Vec::new(),
+            };
+            // Statements don't include a trailing semicolon
+            let len = if frag_spec == "stmt" && len > 1 && is_punct(position + len - 1, ';') {
+                len - 1
+            } else {
+                len
+            };
+            let group = Group::new(Delimiter::None, bound(position + len));
+            return vec![(position + len, TokenTree::Group(group).into())];
+        }
+    };
+
+    vec![(end, bound(end))]
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use quote::quote;
+
+    fn matches(pattern: TokenStream, invocation: TokenStream) -> bool {
+        let pattern: MacroMatcher = syn::parse2(pattern).expect("test pattern did not parse");
+        let tokens = InvocationToken::from_tokens(invocation).expect("test invocation had a repetition");
+        could_match(&pattern, &tokens)
+    }
+
+    #[test]
+    fn literal_tokens() {
+        assert!(matches(quote! { a + (b) }, quote! { a + (b) }));
+        assert!(!matches(quote! { a + (b) }, quote! { a + [b] }));
+        assert!(!matches(quote! { a }, quote! { a b }));
+    }
+
+    #[test]
+    fn fragments() {
+        assert!(matches(quote! { $x:ident, $y:literal }, quote! { foo, -12 }));
+        assert!(matches(quote! { $e:expr ; $t:ty }, quote! { 1 + f(2) ; Vec<u8> }));
+        assert!(matches(quote! { $v:vis fn }, quote! { fn }));
+        assert!(!matches(quote! { $x:ident }, quote! { 12 }));
+        assert!(!matches(quote! { $e:expr ; }, quote! { ; }));
+    }
+
+    #[test]
+    fn repetitions() {
+        assert!(matches(quote! { $($x:ident),* }, quote! {}));
+        assert!(matches(quote! { $($x:ident),* }, quote! { a, b, c }));
+        assert!(!matches(quote! { $($x:ident),+ }, quote! {}));
+        assert!(!matches(quote! { $($x:ident),* }, quote! { a, b, }));
+        assert!(matches(quote! { $($x:tt)* end }, quote! { a b end }));
+    }
+
+    #[test]
+    fn lifetimes_are_single_token_trees() {
+        assert!(matches(quote! { $l:tt }, quote! { 'a }));
+        assert!(matches(quote! { $a:tt $b:tt }, quote! { 'a 'b }));
+        assert!(!matches(quote! { $a:tt $b:tt }, quote! { 'a }));
+        assert!(matches(quote! { $l:lifetime , $t:tt }, quote! { 'static, 'a }));
+    }
+
+    #[test]
+    fn metavariables_match_anything() {
+        assert!(matches(quote! { a $x:literal }, quote! { $y $z }));
+        assert!(matches(quote! { $e:expr ; }, quote! { $y ; }));
+        assert!(InvocationToken::from_tokens(quote! { $($y)* }).is_none());
+    }
+
+    #[test]
+    fn bound_fragments() {
+        let pattern: MacroMatcher =
+            syn::parse2(quote! { $a:ident $($b:literal),* ; $e:expr }).expect("test pattern did not parse");
+        let bindings = match_bindings(&pattern, quote! { foo 1, 2 ; x + 1 }).expect("pattern did not match");
+
+        let fragment = |binding: &Binding| match binding {
+            Binding::Fragment(tokens) => tokens.to_string(),
+            Binding::Repetition(_) => panic!("expected a fragment"),
+        };
+        assert_eq!(fragment(&bindings["a"]), "foo");
+        assert_eq!(fragment(&bindings["e"]), "x + 1");
+        match &bindings["b"] {
+            Binding::Repetition(values) => {
+                assert_eq!(values.iter().map(fragment).collect::<Vec<_>>(), vec!["1", "2"])
+            }
+            Binding::Fragment(_) => panic!("expected a repetition"),
+        }
+
+        assert!(match_bindings(&pattern, quote! { foo 1, 2 ; }).is_none());
+    }
+}
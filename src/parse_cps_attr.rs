@@ -0,0 +1,86 @@
+use proc_macro2::{TokenStream, TokenTree};
+use quote::quote;
+use syn::parse::Parser;
+use syn::{LitStr, Path};
+
+/// The arguments given to the `#[cps(...)]` attribute.
+#[derive(Clone, Default)]
+pub struct CPSAttr {
+    /// The path that the `cps` crate can be reached through, given as `#[cps(crate = "path::to::cps")]`.
+    pub crate_path: Option<TokenStream>,
+}
+
+impl CPSAttr {
+    pub fn parse(attr: TokenStream) -> syn::Result<Self> {
+        let mut res = Self::default();
+
+        let parser = syn::meta::parser(|meta| {
+            if meta.path.is_ident("crate") {
+                if res.crate_path.is_some() {
+                    return Err(meta.error("duplicate `crate` argument"));
+                }
+                let path: LitStr = meta.value()?.parse()?;
+                res.crate_path = Some(parse_crate_path(&path)?);
+                Ok(())
+            } else {
+                Err(meta.error("unsupported cps attribute argument, expected `crate = \"...\"`"))
+            }
+        });
+        parser.parse2(attr)?;
+
+        Ok(res)
+    }
+
+    /// The path to use when referring to items in the `cps` crate from generated code.
+    pub fn crate_path(&self) -> TokenStream {
+        match &self.crate_path {
+            Some(path) => path.clone(),
+            None => quote! { ::cps },
+        }
+    }
+
+    /// Resolves a macro path written by the user, substituting the configured crate path for a leading `cps::`.
+    pub fn resolve_macro_path(&self, path: &Path) -> TokenStream {
+        let crate_path = match &self.crate_path {
+            Some(crate_path) => crate_path,
+            None => return quote! { #path },
+        };
+
+        let mut segments = path.segments.iter();
+        match segments.next() {
+            Some(first)
+                if path.leading_colon.is_none()
+                    && first.ident == "cps"
+                    && first.arguments.is_none()
+                    && path.segments.len() > 1 =>
+            {
+                quote! { #crate_path #(:: #segments)* }
+            }
+            _ => quote! { #path },
+        }
+    }
+}
+
+/// Parses a crate path given as a string. As well as regular paths, paths beginning with `$crate` are allowed
+/// so that crates which re-export `cps` can refer to themselves from within exported macros.
+fn parse_crate_path(path: &LitStr) -> syn::Result<TokenStream> {
+    let tokens: TokenStream = path.parse()?;
+
+    let mut iter = tokens.clone().into_iter();
+    let rest = match (iter.next(), iter.clone().next()) {
+        (Some(TokenTree::Punct(dollar)), Some(TokenTree::Ident(krate)))
+            if dollar.as_char() == '$' && krate == "crate" =>
+        {
+            iter.collect::<TokenStream>()
+        }
+        _ => tokens.clone(),
+    };
+    syn::parse2::<Path>(rest).map_err(|e| {
+        syn::Error::new(
+            path.span(),
+            format!("expected a path to the cps crate: {}", e),
+        )
+    })?;
+
+    Ok(tokens)
+}
@@ -32,28 +32,28 @@ impl Parse for Divider {
     }
 }
 
-/// An identifier enclosed in parenthesis `()`.
+/// The path to a macro enclosed in parenthesis `()`.
 #[derive(Clone)]
-pub struct ParenthesizedIdent {
+pub struct ParenthesizedPath {
     _paren: Paren,
-    pub ident: Ident,
+    pub path: TokenStream,
 }
 
-impl Parse for ParenthesizedIdent {
+impl Parse for ParenthesizedPath {
     fn parse(input: ParseStream) -> syn::Result<Self> {
-        let (paren, ident) = parse_paren(input)?;
+        let (paren, path) = parse_paren(input)?;
         Ok(Self {
             _paren: paren,
-            ident: syn::parse2(ident)?,
+            path,
         })
     }
 }
 
-impl ToTokens for ParenthesizedIdent {
+impl ToTokens for ParenthesizedPath {
     fn to_tokens(&self, tokens: &mut TokenStream) {
-        let Self { ident, .. } = self;
+        let Self { path, .. } = self;
         *tokens = quote!(
-            #tokens (#ident)
+            #tokens (#path)
         )
     }
 }
@@ -153,7 +153,7 @@ pub struct MacroInput {
     _marker: Token![@],
     ident: Ident,
     _div1: Divider,
-    pub program: Punctuated<ParenthesizedIdent, Token![|]>,
+    pub program: Punctuated<ParenthesizedPath, Token![|]>,
     _div2: Divider,
     pub stack: Vec<(Vec<StackElement>, Token![|])>,
 }
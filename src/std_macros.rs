@@ -1,12 +1,31 @@
+pub mod arithmetic;
+pub mod case;
+pub mod classify;
 pub mod concat;
+pub mod count;
+pub mod env;
+pub mod eval_const;
+pub mod format;
+pub mod ident;
 pub mod include;
+pub mod include_bytes;
 pub mod include_str;
+pub mod logic;
+pub mod option_env;
+pub mod paths;
+pub mod read_dir;
 pub mod stringify;
+pub mod strings;
+pub mod unstringify;
+
+mod lex_errors;
+mod literals;
+mod options;
 
 macro_rules! impl_std_cps {
     (
         $(use $import:path;)*
-        fn $name:ident($param_ident:ident : $param_ty:path $(,)?) -> $ret_ty:path {
+        fn $name:ident($param_ident:ident : $param_ty:path $(,)?) -> syn::Result<$ret_ty:path> {
             $( $impl_tt:tt )*
         }
     ) => {
@@ -23,7 +42,7 @@ macro_rules! impl_std_cps {
                 type Input = $param_ty;
                 type Output = $ret_ty;
 
-                fn step($param_ident: $param_ty) -> $ret_ty {
+                fn step($param_ident: $param_ty) -> syn::Result<$ret_ty> {
                     $( $impl_tt )*
                 }
             }
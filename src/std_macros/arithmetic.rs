@@ -0,0 +1,338 @@
+use super::literals::{bool_token, int_literal, is_float_suffix, u128_literal, FloatLit, IntLit};
+use crate::cps_macro::flatten_none_groups;
+use proc_macro2::{Literal, Span, TokenStream, TokenTree};
+use std::cmp::Ordering;
+use syn::parse::{Parse, ParseStream, Parser};
+use syn::punctuated::Punctuated;
+use syn::spanned::Spanned;
+use syn::Token;
+
+fn parse_args(tokens: TokenStream) -> syn::Result<(Vec<IntLit>, Option<&'static str>, Span)> {
+    let tokens = flatten_none_groups(tokens);
+    let span = tokens.span();
+    let args: Vec<IntLit> = Punctuated::<IntLit, Token![,]>::parse_terminated
+        .parse2(tokens)?
+        .into_iter()
+        .collect();
+    if args.is_empty() {
+        return Err(syn::Error::new(span, "expected at least one integer literal"));
+    }
+    let suffix = IntLit::common_suffix(&args)?;
+    // Unsuffixed arguments take the shared type, so must be in its range
+    if let Some(suffix) = suffix {
+        if let Some(arg) = args.iter().find(|arg| !arg.fits(suffix)) {
+            return Err(arg.out_of_range(Some(suffix)));
+        }
+    }
+    Ok((args, suffix, span))
+}
+
+fn parse_binary_args(tokens: TokenStream) -> syn::Result<(Vec<IntLit>, Option<&'static str>, Span)> {
+    let (args, suffix, span) = parse_args(tokens)?;
+    match args.len() {
+        2 => Ok((args, suffix, span)),
+        _ => Err(syn::Error::new(span, "expected two integer literals")),
+    }
+}
+
+/// The checked operations used to combine arguments, for values that fit in an `i128` and for those that need a
+/// `u128`.
+type Ops = (fn(i128, i128) -> Option<i128>, fn(u128, u128) -> Option<u128>);
+
+/// Combines every argument with a checked operation, returning a literal with the arguments' type. Arguments are
+/// combined as `i128`s, unless they have the type `u128` or are too large for an `i128`.
+fn apply(args: &[IntLit], suffix: Option<&'static str>, span: Span, name: &str, ops: Ops) -> syn::Result<TokenTree> {
+    let overflow = || syn::Error::new(span, format!("attempt to {} with overflow", name));
+    let (signed, unsigned) = ops;
+
+    if suffix != Some("u128") && args.iter().all(|arg| arg.to_i128().is_some()) {
+        let mut values = args.iter().filter_map(IntLit::to_i128);
+        let first = values.next().expect("parsed at least one argument");
+        let value = values.try_fold(first, signed).ok_or_else(overflow)?;
+        return int_literal(value, suffix, span);
+    }
+
+    let values = args
+        .iter()
+        .map(|arg| arg.to_u128().ok_or_else(|| arg.out_of_range(Some("u128"))))
+        .collect::<syn::Result<Vec<_>>>()?;
+    let value = values[1..].iter().try_fold(values[0], |acc, value| unsigned(acc, *value)).ok_or_else(overflow)?;
+    match (suffix, i128::try_from(value)) {
+        (None | Some("u128"), _) => Ok(u128_literal(value, suffix)),
+        (Some(_), Ok(value)) => int_literal(value, suffix, span),
+        (Some(suffix), Err(_)) => Err(syn::Error::new(
+            span,
+            format!("`{}` is out of range for `{}`", value, suffix),
+        )),
+    }
+}
+
+fn fold(tokens: TokenStream, name: &str, ops: Ops) -> syn::Result<TokenTree> {
+    let (args, suffix, span) = parse_args(tokens)?;
+    apply(&args, suffix, span, name, ops)
+}
+
+fn divide(tokens: TokenStream, name: &str, ops: Ops) -> syn::Result<TokenTree> {
+    let (args, suffix, span) = parse_binary_args(tokens)?;
+    if args[1].magnitude == 0 {
+        return Err(syn::Error::new(span, format!("attempt to {} by zero", name)));
+    }
+    apply(&args, suffix, span, name, ops)
+}
+
+/// Finds the smallest or largest argument, returning it with the arguments' type.
+fn select(tokens: TokenStream, order: Ordering) -> syn::Result<TokenTree> {
+    let (args, suffix, span) = parse_args(tokens)?;
+    let selected = args
+        .iter()
+        .reduce(|acc, arg| if arg.cmp_value(acc) == order { arg } else { acc })
+        .expect("parsed at least one argument");
+    match (suffix, selected.to_i128()) {
+        (None | Some("u128"), _) if !selected.negative => Ok(u128_literal(selected.magnitude, suffix)),
+        (_, Some(value)) => int_literal(value, suffix, span),
+        (_, None) => Err(selected.out_of_range(suffix)),
+    }
+}
+
+/// A literal that can be ordered against other literals of the same kind.
+enum OrderedLit {
+    Int(IntLit),
+    Float(f64, String, Span),
+    Str(String, Span),
+    Char(char, Span),
+}
+
+impl Parse for OrderedLit {
+    fn parse(input: ParseStream) -> syn::Result<Self> {
+        let negated = input.parse::<Option<Token![-]>>()?.is_some();
+        let literal: Literal = input
+            .parse()
+            .map_err(|_| input.error("expected an integer, float, string or character literal"))?;
+        let span = literal.span();
+        match litrs::Literal::from(&literal) {
+            litrs::Literal::Integer(int) if !is_float_suffix(int.suffix()) => {
+                Ok(Self::Int(IntLit::from_literal(negated, &literal)?))
+            }
+            litrs::Literal::Integer(_) | litrs::Literal::Float(_) => {
+                let float = FloatLit::from_literal(negated, &literal)?;
+                Ok(Self::Float(float.value, float.suffix, span))
+            }
+            litrs::Literal::String(s) if !negated => Ok(Self::Str(s.value().to_owned(), span)),
+            litrs::Literal::Char(c) if !negated => Ok(Self::Char(c.value(), span)),
+            _ => Err(syn::Error::new(
+                span,
+                "expected an integer, float, string or character literal",
+            )),
+        }
+    }
+}
+
+impl OrderedLit {
+    fn kind(&self) -> &'static str {
+        match self {
+            Self::Int(_) => "an integer",
+            Self::Float(..) => "a float",
+            Self::Str(..) => "a string",
+            Self::Char(..) => "a character",
+        }
+    }
+
+    fn span(&self) -> Span {
+        match self {
+            Self::Int(int) => int.span,
+            Self::Float(_, _, span) | Self::Str(_, span) | Self::Char(_, span) => *span,
+        }
+    }
+}
+
+/// Orders two literals of the same kind and type.
+fn order(lhs: &OrderedLit, rhs: &OrderedLit) -> syn::Result<Ordering> {
+    match (lhs, rhs) {
+        (OrderedLit::Int(lhs), OrderedLit::Int(rhs)) => {
+            IntLit::common_suffix(&[lhs.clone(), rhs.clone()])?;
+            Ok(lhs.cmp_value(rhs))
+        }
+        (OrderedLit::Float(lhs, lhs_suffix, _), OrderedLit::Float(rhs, rhs_suffix, span)) => {
+            if !lhs_suffix.is_empty() && !rhs_suffix.is_empty() && lhs_suffix != rhs_suffix {
+                return Err(syn::Error::new(
+                    *span,
+                    format!("mismatched float types, expected `{}` but found `{}`", lhs_suffix, rhs_suffix),
+                ));
+            }
+            Ok(lhs.total_cmp(rhs))
+        }
+        (OrderedLit::Str(lhs, _), OrderedLit::Str(rhs, _)) => Ok(lhs.cmp(rhs)),
+        (OrderedLit::Char(lhs, _), OrderedLit::Char(rhs, _)) => Ok(lhs.cmp(rhs)),
+        _ => Err(syn::Error::new(
+            rhs.span(),
+            format!("cannot compare {} literal with {} literal", lhs.kind(), rhs.kind()),
+        )),
+    }
+}
+
+fn compare(tokens: TokenStream, op: fn(Ordering) -> bool) -> syn::Result<TokenTree> {
+    let tokens = flatten_none_groups(tokens);
+    let span = tokens.span();
+    let args: Vec<OrderedLit> = Punctuated::<OrderedLit, Token![,]>::parse_terminated
+        .parse2(tokens)?
+        .into_iter()
+        .collect();
+    match args.as_slice() {
+        [lhs, rhs] => Ok(bool_token(op(order(lhs, rhs)?))),
+        _ => Err(syn::Error::new(span, "expected two literals")),
+    }
+}
+
+super::impl_std_cps!(
+    fn add(tokens: proc_macro2::TokenStream) -> syn::Result<proc_macro2::TokenTree> {
+        fold(tokens, "add", (i128::checked_add, u128::checked_add))
+    }
+);
+
+super::impl_std_cps!(
+    fn sub(tokens: proc_macro2::TokenStream) -> syn::Result<proc_macro2::TokenTree> {
+        let (args, suffix, span) = parse_binary_args(tokens)?;
+        apply(&args, suffix, span, "subtract", (i128::checked_sub, u128::checked_sub))
+    }
+);
+
+super::impl_std_cps!(
+    fn mul(tokens: proc_macro2::TokenStream) -> syn::Result<proc_macro2::TokenTree> {
+        fold(tokens, "multiply", (i128::checked_mul, u128::checked_mul))
+    }
+);
+
+super::impl_std_cps!(
+    fn div(tokens: proc_macro2::TokenStream) -> syn::Result<proc_macro2::TokenTree> {
+        divide(tokens, "divide", (i128::checked_div, u128::checked_div))
+    }
+);
+
+super::impl_std_cps!(
+    fn rem(tokens: proc_macro2::TokenStream) -> syn::Result<proc_macro2::TokenTree> {
+        divide(tokens, "calculate the remainder", (i128::checked_rem, u128::checked_rem))
+    }
+);
+
+super::impl_std_cps!(
+    fn min(tokens: proc_macro2::TokenStream) -> syn::Result<proc_macro2::TokenTree> {
+        select(tokens, Ordering::Less)
+    }
+);
+
+super::impl_std_cps!(
+    fn max(tokens: proc_macro2::TokenStream) -> syn::Result<proc_macro2::TokenTree> {
+        select(tokens, Ordering::Greater)
+    }
+);
+
+super::impl_std_cps!(
+    fn lt(tokens: proc_macro2::TokenStream) -> syn::Result<proc_macro2::TokenTree> {
+        compare(tokens, Ordering::is_lt)
+    }
+);
+
+super::impl_std_cps!(
+    fn le(tokens: proc_macro2::TokenStream) -> syn::Result<proc_macro2::TokenTree> {
+        compare(tokens, Ordering::is_le)
+    }
+);
+
+super::impl_std_cps!(
+    fn gt(tokens: proc_macro2::TokenStream) -> syn::Result<proc_macro2::TokenTree> {
+        compare(tokens, Ordering::is_gt)
+    }
+);
+
+super::impl_std_cps!(
+    fn ge(tokens: proc_macro2::TokenStream) -> syn::Result<proc_macro2::TokenTree> {
+        compare(tokens, Ordering::is_ge)
+    }
+);
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use quote::quote;
+
+    fn result(res: syn::Result<TokenTree>) -> String {
+        match res {
+            Ok(tt) => tt.to_string(),
+            Err(e) => e.to_string(),
+        }
+    }
+
+    #[test]
+    fn operations() {
+        let add: Ops = (i128::checked_add, u128::checked_add);
+        assert_eq!(result(fold(quote! { 1, 2u8, 3 }, "add", add)), "6u8");
+        assert_eq!(result(divide(quote! { -7, 2 }, "divide", (i128::checked_div, u128::checked_div))), "-3");
+        assert_eq!(
+            result(fold(quote! { 340282366920938463463374607431768211454u128, 1 }, "add", add)),
+            "340282366920938463463374607431768211455u128"
+        );
+        assert_eq!(
+            result(fold(quote! { 170141183460469231731687303715884105728, 1 }, "add", add)),
+            "170141183460469231731687303715884105729"
+        );
+        assert_eq!(result(select(quote! { 3, -1i8, 2 }, Ordering::Less)), "-1i8");
+        assert_eq!(
+            result(select(quote! { 1u128, 340282366920938463463374607431768211455 }, Ordering::Greater)),
+            "340282366920938463463374607431768211455u128"
+        );
+        assert_eq!(
+            result(select(quote! { 1, -170141183460469231731687303715884105728 }, Ordering::Less)),
+            "-170141183460469231731687303715884105728"
+        );
+        assert_eq!(result(compare(quote! { 1usize, 2 }, Ordering::is_lt)), "true");
+        assert_eq!(result(compare(quote! { -1.5, 0.5e1 }, Ordering::is_lt)), "true");
+        assert_eq!(result(compare(quote! { "b", "ab" }, Ordering::is_le)), "false");
+        assert_eq!(result(compare(quote! { 'b', 'a' }, Ordering::is_gt)), "true");
+    }
+
+    #[test]
+    fn errors() {
+        let add: Ops = (i128::checked_add, u128::checked_add);
+        assert_eq!(
+            result(fold(quote! { 200u8, 100 }, "add", add)),
+            "`300` is out of range for `u8`"
+        );
+        assert_eq!(
+            result(fold(quote! { 300, 1u8, -300 }, "add", add)),
+            "`300` is out of range for `u8`"
+        );
+        assert_eq!(
+            result(fold(quote! { 1u8, 1u16 }, "add", add)),
+            "mismatched integer types, expected `u8` but found `u16`"
+        );
+        assert_eq!(
+            result(divide(quote! { 1, 0 }, "divide", (i128::checked_div, u128::checked_div))),
+            "attempt to divide by zero"
+        );
+        assert_eq!(
+            result(fold(quote! { 340282366920938463463374607431768211455u128, 1 }, "add", add)),
+            "attempt to add with overflow"
+        );
+        assert_eq!(
+            result(fold(quote! { 340282366920938463463374607431768211455, -1 }, "add", add)),
+            "`-1` is out of range for `u128`"
+        );
+        assert_eq!(
+            result(select(quote! { 300, 1u8 }, Ordering::Greater)),
+            "`300` is out of range for `u8`"
+        );
+        assert_eq!(result(select(quote! { 0u8, -1 }, Ordering::Less)), "`-1` is out of range for `u8`");
+        assert_eq!(result(select(quote! { 300, 1u8 }, Ordering::Less)), "`300` is out of range for `u8`");
+        assert_eq!(result(select(quote! { 0u8, -1 }, Ordering::Greater)), "`-1` is out of range for `u8`");
+        assert_eq!(result(compare(quote! { 1 }, Ordering::is_lt)), "expected two literals");
+        assert_eq!(
+            result(compare(quote! { 1, "1" }, Ordering::is_lt)),
+            "cannot compare an integer literal with a string literal"
+        );
+        assert_eq!(
+            result(compare(quote! { 1f32, 1f64 }, Ordering::is_lt)),
+            "mismatched float types, expected `f32` but found `f64`"
+        );
+        assert_eq!(result(fold(quote! { 1.5 }, "add", add)), "expected an integer literal");
+    }
+}
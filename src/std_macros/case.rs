@@ -0,0 +1,192 @@
+use super::ident::{is_keyword, new_ident};
+use super::literals::StrLit;
+use crate::cps_macro::flatten_none_groups;
+use proc_macro2::{Literal, Span, TokenStream, TokenTree};
+
+/// The input to a case conversion: an identifier or a string literal, which is converted to the same kind of token.
+enum CaseInput {
+    Ident { text: String, raw: bool, span: Span },
+    Str { text: String, span: Span },
+}
+
+impl CaseInput {
+    fn parse(tokens: TokenStream) -> syn::Result<Self> {
+        let tokens = flatten_none_groups(tokens);
+        let err = || syn::Error::new_spanned(&tokens, "expected a single identifier or string literal");
+
+        let mut iter = tokens.clone().into_iter();
+        let token = match (iter.next(), iter.next()) {
+            (Some(token), None) => token,
+            _ => return Err(err()),
+        };
+        match &token {
+            TokenTree::Ident(i) => {
+                let text = i.to_string();
+                Ok(match text.strip_prefix("r#") {
+                    Some(text) => Self::Ident { text: text.to_owned(), raw: true, span: i.span() },
+                    None => Self::Ident { text, raw: false, span: i.span() },
+                })
+            }
+            TokenTree::Literal(l) => StrLit::from_literal(l)
+                .map(|s| Self::Str { text: s.value, span: s.span })
+                .map_err(|_| err()),
+            _ => Err(err()),
+        }
+    }
+
+    /// Converts the words of the input and rebuilds a token of the same kind. Identifiers that become keywords are
+    /// made raw.
+    fn convert(self, convert: impl Fn(&[String]) -> String) -> syn::Result<TokenTree> {
+        match self {
+            Self::Ident { text, raw, span } => {
+                let converted = convert_words(&text, convert);
+                let raw = raw || is_keyword(&converted);
+                Ok(TokenTree::Ident(new_ident(&converted, span, raw)?))
+            }
+            Self::Str { text, span } => {
+                let mut literal = Literal::string(&convert_words(&text, convert));
+                literal.set_span(span);
+                Ok(TokenTree::Literal(literal))
+            }
+        }
+    }
+}
+
+/// Converts the words of the text, keeping any leading and trailing underscores, so `_private` stays private and
+/// `__init__` keeps its dunders.
+fn convert_words(text: &str, convert: impl Fn(&[String]) -> String) -> String {
+    let trimmed = text.trim_start_matches('_');
+    let prefix = &text[..text.len() - trimmed.len()];
+    let inner = trimmed.trim_end_matches('_');
+    let suffix = &trimmed[inner.len()..];
+    format!("{}{}{}", prefix, convert(&words(inner)), suffix)
+}
+
+/// Splits text into words at underscores, hyphens, whitespace and changes in case. A run of capitals is treated as
+/// one word, so `HTTPServer` is split into `HTTP` and `Server`.
+fn words(text: &str) -> Vec<String> {
+    let chars: Vec<char> = text.chars().collect();
+    let mut words = Vec::new();
+    let mut word = String::new();
+    for (i, c) in chars.iter().copied().enumerate() {
+        if c == '_' || c == '-' || c.is_whitespace() {
+            if !word.is_empty() {
+                words.push(std::mem::take(&mut word));
+            }
+            continue;
+        }
+
+        if let (false, Some(prev)) = (word.is_empty(), i.checked_sub(1).map(|i| chars[i])) {
+            let next_is_lower = chars.get(i + 1).map(|next| next.is_lowercase()).unwrap_or(false);
+            let starts_word = c.is_uppercase()
+                && ((prev.is_lowercase() || prev.is_numeric()) || (prev.is_uppercase() && next_is_lower));
+            if starts_word {
+                words.push(std::mem::take(&mut word));
+            }
+        }
+        word.push(c);
+    }
+    if !word.is_empty() {
+        words.push(word);
+    }
+    words
+}
+
+fn capitalize(word: &str) -> String {
+    let mut chars = word.chars();
+    match chars.next() {
+        Some(first) => first.to_uppercase().chain(chars.flat_map(char::to_lowercase)).collect(),
+        None => String::new(),
+    }
+}
+
+fn snake_case(words: &[String]) -> String {
+    words.iter().map(|word| word.to_lowercase()).collect::<Vec<_>>().join("_")
+}
+
+fn upper_snake_case(words: &[String]) -> String {
+    words.iter().map(|word| word.to_uppercase()).collect::<Vec<_>>().join("_")
+}
+
+fn camel_case(words: &[String]) -> String {
+    words
+        .iter()
+        .enumerate()
+        .map(|(i, word)| if i == 0 { word.to_lowercase() } else { capitalize(word) })
+        .collect()
+}
+
+fn pascal_case(words: &[String]) -> String {
+    words.iter().map(|word| capitalize(word)).collect()
+}
+
+fn kebab_case(words: &[String]) -> String {
+    words.iter().map(|word| word.to_lowercase()).collect::<Vec<_>>().join("-")
+}
+
+super::impl_std_cps!(
+    fn to_snake_case(tokens: proc_macro2::TokenStream) -> syn::Result<proc_macro2::TokenTree> {
+        CaseInput::parse(tokens)?.convert(snake_case)
+    }
+);
+
+super::impl_std_cps!(
+    fn to_upper_snake_case(tokens: proc_macro2::TokenStream) -> syn::Result<proc_macro2::TokenTree> {
+        CaseInput::parse(tokens)?.convert(upper_snake_case)
+    }
+);
+
+super::impl_std_cps!(
+    fn to_camel_case(tokens: proc_macro2::TokenStream) -> syn::Result<proc_macro2::TokenTree> {
+        CaseInput::parse(tokens)?.convert(camel_case)
+    }
+);
+
+super::impl_std_cps!(
+    fn to_pascal_case(tokens: proc_macro2::TokenStream) -> syn::Result<proc_macro2::TokenTree> {
+        CaseInput::parse(tokens)?.convert(pascal_case)
+    }
+);
+
+super::impl_std_cps!(
+    fn to_kebab_case(tokens: proc_macro2::TokenStream) -> syn::Result<proc_macro2::TokenTree> {
+        match CaseInput::parse(tokens)? {
+            CaseInput::Ident { span, .. } => Err(syn::Error::new(
+                span,
+                "kebab-case can't be an identifier, pass a string literal instead",
+            )),
+            input => input.convert(kebab_case),
+        }
+    }
+);
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn convert(convert: fn(&[String]) -> String, text: &str) -> String {
+        convert_words(text, convert)
+    }
+
+    #[test]
+    fn word_boundaries() {
+        assert_eq!(words("HTTPServer_error-code v2Name"), vec!["HTTP", "Server", "error", "code", "v2", "Name"]);
+        assert_eq!(words("__leading"), vec!["leading"]);
+    }
+
+    #[test]
+    fn conversions() {
+        assert_eq!(convert(snake_case, "MyHTTPServer"), "my_http_server");
+        assert_eq!(convert(upper_snake_case, "myField"), "MY_FIELD");
+        assert_eq!(convert(camel_case, "my_field_name"), "myFieldName");
+        assert_eq!(convert(pascal_case, "my-field name"), "MyFieldName");
+        assert_eq!(convert(kebab_case, "MyFieldName"), "my-field-name");
+    }
+
+    #[test]
+    fn surrounding_underscores_kept() {
+        assert_eq!(convert(snake_case, "_privateField"), "_private_field");
+        assert_eq!(convert(pascal_case, "__init__"), "__Init__");
+        assert_eq!(convert(upper_snake_case, "_"), "_");
+    }
+}
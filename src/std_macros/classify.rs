@@ -0,0 +1,174 @@
+use super::ident;
+use super::literals::bool_token;
+use crate::cps_macro::flatten_none_groups;
+use proc_macro2::{Delimiter, Ident, Span, TokenStream, TokenTree};
+use syn::spanned::Spanned;
+
+/// The single token tree in some tokens, looking through invisible groups.
+fn single(tokens: TokenStream) -> Option<TokenTree> {
+    let mut tokens = flatten_none_groups(tokens).into_iter();
+    match (tokens.next(), tokens.next()) {
+        (Some(tt), None) => Some(tt),
+        _ => None,
+    }
+}
+
+/// The kind of a literal, accepting the same tokens as a `literal` fragment: an optionally negated literal, `true` or
+/// `false`.
+fn literal_kind_name(tokens: TokenStream) -> Option<&'static str> {
+    let tokens: Vec<TokenTree> = flatten_none_groups(tokens).into_iter().collect();
+    let (negated, literal) = match tokens.as_slice() {
+        [TokenTree::Ident(i)] if i == "true" || i == "false" => return Some("bool"),
+        [TokenTree::Literal(l)] => (false, l),
+        [TokenTree::Punct(p), TokenTree::Literal(l)] if p.as_char() == '-' => (true, l),
+        _ => return None,
+    };
+    match litrs::Literal::from(literal) {
+        litrs::Literal::Integer(_) => Some("int"),
+        litrs::Literal::Float(_) => Some("float"),
+        litrs::Literal::Char(_) if !negated => Some("char"),
+        litrs::Literal::String(_) if !negated => Some("str"),
+        litrs::Literal::Byte(_) if !negated => Some("byte"),
+        litrs::Literal::ByteString(_) if !negated => Some("byte_str"),
+        _ => None,
+    }
+}
+
+fn delimiter_name(tokens: TokenStream) -> Option<&'static str> {
+    match single(tokens)? {
+        TokenTree::Group(g) => match g.delimiter() {
+            Delimiter::Parenthesis => Some("paren"),
+            Delimiter::Bracket => Some("bracket"),
+            Delimiter::Brace => Some("brace"),
+            Delimiter::None => None,
+        },
+        _ => None,
+    }
+}
+
+fn is_ident_token(tokens: TokenStream) -> bool {
+    matches!(single(tokens), Some(TokenTree::Ident(i)) if !ident::is_keyword(&i.to_string()))
+}
+
+fn is_keyword_token(tokens: TokenStream) -> bool {
+    matches!(single(tokens), Some(TokenTree::Ident(i)) if ident::is_keyword(&i.to_string()))
+}
+
+fn kind_ident(kind: &str) -> Ident {
+    Ident::new(kind, Span::call_site())
+}
+
+super::impl_std_cps!(
+    fn is_ident(tokens: proc_macro2::TokenStream) -> syn::Result<proc_macro2::TokenTree> {
+        Ok(bool_token(is_ident_token(tokens)))
+    }
+);
+
+super::impl_std_cps!(
+    fn is_keyword(tokens: proc_macro2::TokenStream) -> syn::Result<proc_macro2::TokenTree> {
+        Ok(bool_token(is_keyword_token(tokens)))
+    }
+);
+
+super::impl_std_cps!(
+    fn is_literal(tokens: proc_macro2::TokenStream) -> syn::Result<proc_macro2::TokenTree> {
+        Ok(bool_token(literal_kind_name(tokens).is_some()))
+    }
+);
+
+super::impl_std_cps!(
+    fn literal_kind(tokens: proc_macro2::TokenStream) -> syn::Result<proc_macro2::Ident> {
+        let span = tokens.span();
+        literal_kind_name(tokens)
+            .map(kind_ident)
+            .ok_or_else(|| syn::Error::new(span, "expected a literal"))
+    }
+);
+
+super::impl_std_cps!(
+    fn is_lifetime(tokens: proc_macro2::TokenStream) -> syn::Result<proc_macro2::TokenTree> {
+        Ok(bool_token(syn::parse2::<syn::Lifetime>(flatten_none_groups(tokens)).is_ok()))
+    }
+);
+
+super::impl_std_cps!(
+    fn is_group(tokens: proc_macro2::TokenStream) -> syn::Result<proc_macro2::TokenTree> {
+        Ok(bool_token(delimiter_name(tokens).is_some()))
+    }
+);
+
+super::impl_std_cps!(
+    fn group_delimiter(tokens: proc_macro2::TokenStream) -> syn::Result<proc_macro2::Ident> {
+        let span = tokens.span();
+        delimiter_name(tokens)
+            .map(kind_ident)
+            .ok_or_else(|| syn::Error::new(span, "expected a group in parentheses, brackets or braces"))
+    }
+);
+
+super::impl_std_cps!(
+    fn is_empty(tokens: proc_macro2::TokenStream) -> syn::Result<proc_macro2::TokenTree> {
+        Ok(bool_token(flatten_none_groups(tokens).is_empty()))
+    }
+);
+
+super::impl_std_cps!(
+    fn is_type(tokens: proc_macro2::TokenStream) -> syn::Result<proc_macro2::TokenTree> {
+        Ok(bool_token(syn::parse2::<syn::Type>(tokens).is_ok()))
+    }
+);
+
+super::impl_std_cps!(
+    fn is_expr(tokens: proc_macro2::TokenStream) -> syn::Result<proc_macro2::TokenTree> {
+        Ok(bool_token(syn::parse2::<syn::Expr>(tokens).is_ok()))
+    }
+);
+
+super::impl_std_cps!(
+    fn is_path(tokens: proc_macro2::TokenStream) -> syn::Result<proc_macro2::TokenTree> {
+        Ok(bool_token(syn::parse2::<syn::Path>(tokens).is_ok()))
+    }
+);
+
+super::impl_std_cps!(
+    fn is_item(tokens: proc_macro2::TokenStream) -> syn::Result<proc_macro2::TokenTree> {
+        Ok(bool_token(syn::parse2::<syn::Item>(tokens).is_ok()))
+    }
+);
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use quote::quote;
+
+    #[test]
+    fn literals() {
+        assert_eq!(literal_kind_name(quote! { 1u8 }), Some("int"));
+        assert_eq!(literal_kind_name(quote! { -1.5 }), Some("float"));
+        assert_eq!(literal_kind_name(quote! { "a" }), Some("str"));
+        assert_eq!(literal_kind_name(quote! { r#"a"# }), Some("str"));
+        assert_eq!(literal_kind_name(quote! { b'a' }), Some("byte"));
+        assert_eq!(literal_kind_name(quote! { b"a" }), Some("byte_str"));
+        assert_eq!(literal_kind_name(quote! { 'a' }), Some("char"));
+        assert_eq!(literal_kind_name(quote! { false }), Some("bool"));
+        assert_eq!(literal_kind_name(quote! { -"a" }), None);
+        assert_eq!(literal_kind_name(quote! { 1 2 }), None);
+    }
+
+    #[test]
+    fn idents() {
+        assert!(is_ident_token(quote! { foo }));
+        assert!(is_ident_token(quote! { r#fn }));
+        assert!(!is_ident_token(quote! { fn }));
+        assert!(is_keyword_token(quote! { self }));
+        assert!(!is_keyword_token(quote! { foo bar }));
+    }
+
+    #[test]
+    fn groups() {
+        assert_eq!(delimiter_name(quote! { (a, b) }), Some("paren"));
+        assert_eq!(delimiter_name(quote! { [] }), Some("bracket"));
+        assert_eq!(delimiter_name(quote! { {} }), Some("brace"));
+        assert_eq!(delimiter_name(quote! { () () }), None);
+    }
+}
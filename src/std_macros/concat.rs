@@ -55,7 +55,7 @@ impl Parse for ConcatInput {
 super::impl_std_cps!(
     use super::ConcatInput;
 
-    fn concat(tokens: proc_macro2::TokenStream) -> proc_macro2::Literal {
+    fn concat(tokens: proc_macro2::TokenStream) -> syn::Result<proc_macro2::Literal> {
         let err = format!("arguments given were not comma separated: {}", tokens);
         let parsed: ConcatInput = match syn::parse2(tokens) {
             Err(e) => panic!("error parsing concat input: {}, {}", err, e),
@@ -66,6 +66,6 @@ super::impl_std_cps!(
         for token in parsed.args.into_iter() {
             string += &token.to_string();
         }
-        proc_macro2::Literal::string(&string)
+        Ok(proc_macro2::Literal::string(&string))
     }
 );
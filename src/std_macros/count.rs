@@ -0,0 +1,102 @@
+use super::literals::StrLit;
+use crate::cps_macro::flatten_none_groups;
+use proc_macro2::{Literal, TokenStream, TokenTree};
+
+/// Splits tokens on top-level commas, allowing a trailing comma. Commas inside groups don't separate items.
+pub fn split_items(tokens: TokenStream) -> syn::Result<Vec<TokenStream>> {
+    let mut items = vec![TokenStream::new()];
+    for tt in tokens {
+        match tt {
+            TokenTree::Punct(p) if p.as_char() == ',' => {
+                if items.last().expect("there is always a current item").is_empty() {
+                    return Err(syn::Error::new(p.span(), "expected an item before this comma"));
+                }
+                items.push(TokenStream::new());
+            }
+            tt => items.last_mut().expect("there is always a current item").extend([tt]),
+        }
+    }
+    if items.last().expect("there is always a current item").is_empty() {
+        items.pop();
+    }
+    Ok(items)
+}
+
+/// Counts the token trees in some tokens. A lifetime like `'a` is a quote and an identifier, but a single token tree.
+fn count_token_trees(tokens: TokenStream) -> usize {
+    let mut tokens = tokens.into_iter().peekable();
+    let mut count = 0;
+    while let Some(tt) = tokens.next() {
+        if let TokenTree::Punct(p) = &tt {
+            if p.as_char() == '\'' && matches!(tokens.peek(), Some(TokenTree::Ident(_))) {
+                tokens.next();
+            }
+        }
+        count += 1;
+    }
+    count
+}
+
+/// The value of a string literal, which may be passed through a `literal` or `expr` fragment.
+fn string_value(tokens: TokenStream) -> syn::Result<String> {
+    syn::parse2::<StrLit>(flatten_none_groups(tokens)).map(|s| s.value)
+}
+
+super::impl_std_cps!(
+    fn count_tts(tokens: proc_macro2::TokenStream) -> syn::Result<proc_macro2::Literal> {
+        Ok(Literal::usize_unsuffixed(count_token_trees(tokens)))
+    }
+);
+
+super::impl_std_cps!(
+    fn count_items(tokens: proc_macro2::TokenStream) -> syn::Result<proc_macro2::Literal> {
+        Ok(Literal::usize_unsuffixed(split_items(tokens)?.len()))
+    }
+);
+
+super::impl_std_cps!(
+    fn str_len(tokens: proc_macro2::TokenStream) -> syn::Result<proc_macro2::Literal> {
+        Ok(Literal::usize_unsuffixed(string_value(tokens)?.chars().count()))
+    }
+);
+
+super::impl_std_cps!(
+    fn str_byte_len(tokens: proc_macro2::TokenStream) -> syn::Result<proc_macro2::Literal> {
+        Ok(Literal::usize_unsuffixed(string_value(tokens)?.len()))
+    }
+);
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use quote::quote;
+
+    fn count(tokens: TokenStream) -> Result<usize, String> {
+        split_items(tokens).map(|items| items.len()).map_err(|e| e.to_string())
+    }
+
+    #[test]
+    fn items() {
+        assert_eq!(count(quote! {}), Ok(0));
+        assert_eq!(count(quote! { a }), Ok(1));
+        assert_eq!(count(quote! { a, b c, }), Ok(2));
+        assert_eq!(count(quote! { (a, b), [c, d], e }), Ok(3));
+        assert_eq!(count(quote! { a,, b }), Err("expected an item before this comma".to_owned()));
+        assert_eq!(count(quote! { , }), Err("expected an item before this comma".to_owned()));
+    }
+
+    #[test]
+    fn token_trees() {
+        assert_eq!(count_token_trees(quote! { a (b c) + }), 3);
+        assert_eq!(count_token_trees(quote! { 'a 'static: 'a }), 4);
+        assert_eq!(count_token_trees(quote! { 'x' 'a }), 2);
+    }
+
+    #[test]
+    fn strings() {
+        assert_eq!(string_value(quote! { "héllo" }).map(|s| s.chars().count()).ok(), Some(5));
+        assert_eq!(string_value(quote! { r"a\n" }).ok(), Some("a\\n".to_owned()));
+        assert!(string_value(quote! { b"bytes" }).is_err());
+        assert!(string_value(quote! { "a" "b" }).is_err());
+    }
+}
@@ -0,0 +1,59 @@
+use super::literals::StrLit;
+use crate::cps_macro::flatten_none_groups;
+use proc_macro2::Literal;
+use syn::parse::{Parse, ParseStream};
+use syn::Token;
+
+/// The name of an environment variable, and for `env!` an optional message to show if it isn't set.
+pub struct EnvInput {
+    pub name: StrLit,
+    pub message: Option<StrLit>,
+}
+
+impl Parse for EnvInput {
+    fn parse(input: ParseStream) -> syn::Result<Self> {
+        let name: StrLit = input.parse()?;
+        let mut message = None;
+        if input.parse::<Option<Token![,]>>()?.is_some() && !input.is_empty() {
+            message = Some(input.parse()?);
+            input.parse::<Option<Token![,]>>()?;
+        }
+        if !input.is_empty() {
+            return Err(input.error("unexpected extra argument"));
+        }
+
+        Ok(Self { name, message })
+    }
+}
+
+/// Reads an environment variable at expansion time, treating variables that aren't unicode as an error.
+pub fn read_var(name: &StrLit) -> syn::Result<Option<String>> {
+    match std::env::var(&name.value) {
+        Ok(value) => Ok(Some(value)),
+        Err(std::env::VarError::NotPresent) => Ok(None),
+        Err(std::env::VarError::NotUnicode(_)) => Err(syn::Error::new(
+            name.span,
+            format!("environment variable `{}` was not valid unicode", name.value),
+        )),
+    }
+}
+
+fn do_env(tokens: proc_macro2::TokenStream) -> syn::Result<Literal> {
+    let input: EnvInput = syn::parse2(flatten_none_groups(tokens))?;
+    match read_var(&input.name)? {
+        Some(value) => Ok(Literal::string(&value)),
+        None => {
+            let message = match input.message {
+                Some(message) => message.value,
+                None => format!("environment variable `{}` not defined at compile time", input.name.value),
+            };
+            Err(syn::Error::new(input.name.span, message))
+        }
+    }
+}
+
+super::impl_std_cps!(
+    fn env(tokens: proc_macro2::TokenStream) -> syn::Result<proc_macro2::Literal> {
+        env::do_env(tokens)
+    }
+);
@@ -0,0 +1,412 @@
+use super::literals::{bool_token, int_literal, int_suffix, int_width, u128_literal, IntLit};
+use proc_macro2::{Literal, Span, TokenTree};
+use syn::spanned::Spanned;
+use syn::{BinOp, Expr, Lit, UnOp};
+
+/// The value of a constant expression.
+#[derive(Clone)]
+enum Value {
+    Int(IntLit),
+    Bool(bool),
+    Char(char),
+    Str(String),
+}
+
+impl Value {
+    fn type_name(&self) -> &'static str {
+        match self {
+            Self::Int(int) => int.suffix.unwrap_or("integer"),
+            Self::Bool(_) => "bool",
+            Self::Char(_) => "char",
+            Self::Str(_) => "&str",
+        }
+    }
+
+    fn into_token(self, span: Span) -> syn::Result<TokenTree> {
+        Ok(match self {
+            Self::Int(int) => match int.to_i128() {
+                Some(value) if int.negative => int_literal(value, int.suffix, span)?,
+                _ => u128_literal(int.magnitude, int.suffix),
+            },
+            Self::Bool(value) => bool_token(value),
+            Self::Char(value) => TokenTree::Literal(Literal::character(value)),
+            Self::Str(value) => TokenTree::Literal(Literal::string(&value)),
+        })
+    }
+}
+
+/// The two's complement bits of an integer, as they would be stored in a `u128`.
+fn to_bits(int: &IntLit) -> u128 {
+    match int.negative {
+        true => int.magnitude.wrapping_neg(),
+        false => int.magnitude,
+    }
+}
+
+/// Converts two's complement bits to an integer type like `as` does, truncating them to the width of the type.
+fn from_bits(bits: u128, suffix: &'static str, span: Span) -> IntLit {
+    let (width, signed) = int_width(suffix);
+    let truncated = match width {
+        128 => bits,
+        width => bits & ((1u128 << width) - 1),
+    };
+    match signed && truncated >> (width - 1) == 1 {
+        true => IntLit::from_i128(((truncated << (128 - width)) as i128) >> (128 - width), Some(suffix), span),
+        false => IntLit::from_u128(truncated, Some(suffix), span),
+    }
+}
+
+/// Checks that the result of an operation fits in its type. Unsuffixed integers can have any value that an `i128` or
+/// a `u128` can hold.
+fn check_range(int: IntLit, op: &str) -> syn::Result<IntLit> {
+    let fits = match (int.suffix, int.negative) {
+        (Some(suffix), _) => int.fits(suffix),
+        (None, true) => int.to_i128().is_some(),
+        (None, false) => true,
+    };
+    match fits {
+        true => Ok(int),
+        false => Err(syn::Error::new(int.span, format!("attempt to {} with overflow", op))),
+    }
+}
+
+fn unify(lhs: Option<&'static str>, rhs: Option<&'static str>, span: Span) -> syn::Result<Option<&'static str>> {
+    match (lhs, rhs) {
+        (Some(lhs), Some(rhs)) if lhs != rhs => Err(syn::Error::new(
+            span,
+            format!("mismatched integer types, expected `{}` but found `{}`", lhs, rhs),
+        )),
+        (lhs, rhs) => Ok(lhs.or(rhs)),
+    }
+}
+
+/// The checked operations used to combine integers, for values that fit in an `i128` and for those that need a
+/// `u128`.
+type Ops = (fn(i128, i128) -> Option<i128>, fn(u128, u128) -> Option<u128>);
+
+/// Combines two integers with a checked operation. As in [`add!`](crate::add), integers are combined as `i128`s,
+/// unless they have the type `u128` or are too large for an `i128`.
+fn apply(
+    lhs: &IntLit,
+    rhs: &IntLit,
+    suffix: Option<&'static str>,
+    span: Span,
+    op: &str,
+    ops: Ops,
+) -> syn::Result<IntLit> {
+    let overflow = || syn::Error::new(span, format!("attempt to {} with overflow", op));
+    let (signed, unsigned) = ops;
+
+    if let (false, Some(lhs), Some(rhs)) = (suffix == Some("u128"), lhs.to_i128(), rhs.to_i128()) {
+        let value = signed(lhs, rhs).ok_or_else(overflow)?;
+        return check_range(IntLit::from_i128(value, suffix, span), op);
+    }
+
+    let lhs = lhs.to_u128().ok_or_else(|| lhs.out_of_range(Some("u128")))?;
+    let rhs = rhs.to_u128().ok_or_else(|| rhs.out_of_range(Some("u128")))?;
+    let value = unsigned(lhs, rhs).ok_or_else(overflow)?;
+    check_range(IntLit::from_u128(value, suffix, span), op)
+}
+
+fn eval(expr: &Expr) -> syn::Result<Value> {
+    let span = expr.span();
+    match expr {
+        Expr::Lit(lit) => match &lit.lit {
+            Lit::Int(int) => eval_int(false, &int.token()),
+            Lit::Bool(b) => Ok(Value::Bool(b.value)),
+            Lit::Char(c) => Ok(Value::Char(c.value())),
+            Lit::Str(s) => Ok(Value::Str(s.value())),
+            _ => Err(syn::Error::new(span, "expected an integer, bool, char or string literal")),
+        },
+        Expr::Paren(paren) => eval(&paren.expr),
+        Expr::Group(group) => eval(&group.expr),
+        Expr::Unary(unary) => match (&unary.op, &*unary.expr) {
+            // Negative literals are read together, so that literals like `-128i8` are in range
+            (UnOp::Neg(_), Expr::Lit(syn::ExprLit { lit: Lit::Int(int), .. })) => eval_int(true, &int.token()),
+            (op, expr) => eval_unary(op, eval(expr)?, span),
+        },
+        Expr::Binary(binary) => {
+            // Both sides of logical operators are evaluated, so that the right hand side is type checked even when it
+            // would be short-circuited
+            eval_binary(&binary.op, eval(&binary.left)?, eval(&binary.right)?, span)
+        }
+        Expr::Cast(cast) => {
+            let target = match &*cast.ty {
+                syn::Type::Path(path) => path.path.get_ident().map(|ident| ident.to_string()),
+                _ => None,
+            };
+            eval_cast(eval(&cast.expr)?, target.as_deref(), span)
+        }
+        _ => Err(syn::Error::new(
+            span,
+            "unsupported expression, expected literals combined with operators, parentheses and casts",
+        )),
+    }
+}
+
+fn is_unsigned(suffix: Option<&str>) -> bool {
+    suffix.map(|suffix| !int_width(suffix).1).unwrap_or(false)
+}
+
+fn eval_int(negated: bool, literal: &Literal) -> syn::Result<Value> {
+    let int = IntLit::from_literal(negated, literal)?;
+    if negated && is_unsigned(int.suffix) {
+        return Err(syn::Error::new(int.span, "cannot negate an unsigned integer"));
+    }
+    let fits = match int.suffix {
+        Some(suffix) => int.fits(suffix),
+        None => !int.negative || int.to_i128().is_some(),
+    };
+    match fits {
+        true => Ok(Value::Int(int)),
+        false => Err(int.out_of_range(int.suffix)),
+    }
+}
+
+fn eval_unary(op: &UnOp, value: Value, span: Span) -> syn::Result<Value> {
+    match (op, value) {
+        (UnOp::Neg(_), Value::Int(int)) => {
+            if is_unsigned(int.suffix) {
+                return Err(syn::Error::new(span, "cannot negate an unsigned integer"));
+            }
+            let overflow = || syn::Error::new(span, "attempt to negate with overflow");
+            let negated = match int.to_i128().map(i128::checked_neg) {
+                Some(Some(value)) => IntLit::from_i128(value, int.suffix, span),
+                // Only a `u128` can hold `-i128::MIN`, which is out of range unless the integer is unsuffixed
+                Some(None) => IntLit::from_u128(int.magnitude, int.suffix, span),
+                None => return Err(overflow()),
+            };
+            Ok(Value::Int(check_range(negated, "negate")?))
+        }
+        (UnOp::Not(_), Value::Bool(value)) => Ok(Value::Bool(!value)),
+        (UnOp::Not(_), Value::Int(int)) => match int.suffix {
+            Some(suffix) => Ok(Value::Int(from_bits(!to_bits(&int), suffix, span))),
+            // Unsuffixed integers are negated as if they were signed, which doesn't depend on their width
+            None => match int.to_i128() {
+                Some(value) => Ok(Value::Int(IntLit::from_i128(!value, None, span))),
+                None => Err(int.out_of_range(Some("i128"))),
+            },
+        },
+        (_, value) => Err(syn::Error::new(
+            span,
+            format!("cannot apply this operator to `{}`", value.type_name()),
+        )),
+    }
+}
+
+fn eval_binary(op: &BinOp, lhs: Value, rhs: Value, span: Span) -> syn::Result<Value> {
+    use Value::*;
+
+    match (op, lhs, rhs) {
+        (BinOp::Shl(_) | BinOp::Shr(_), Int(lhs), Int(rhs)) => {
+            let width = lhs.suffix.map(|suffix| int_width(suffix).0).unwrap_or(128);
+            if rhs.negative || rhs.magnitude >= u128::from(width) {
+                return Err(syn::Error::new(span, "attempt to shift with overflow"));
+            }
+            let amount = rhs.magnitude as u32;
+            let value = match (op, lhs.suffix) {
+                (BinOp::Shr(_), _) => match lhs.to_i128() {
+                    Some(value) if lhs.negative => IntLit::from_i128(value >> amount, lhs.suffix, span),
+                    _ => IntLit::from_u128(lhs.magnitude >> amount, lhs.suffix, span),
+                },
+                // Shifting a value of a known type discards the bits shifted out of it, as in Rust
+                (_, Some(suffix)) => from_bits(to_bits(&lhs) << amount, suffix, span),
+                // Unsuffixed integers have no width to shift out of, so shifting them left is multiplication
+                (_, None) => {
+                    let factor = IntLit::from_u128(1 << amount, None, span);
+                    apply(&lhs, &factor, None, span, "shift left", (i128::checked_mul, u128::checked_mul))?
+                }
+            };
+            Ok(Int(value))
+        }
+        (op, Int(lhs), Int(rhs)) => {
+            let suffix = unify(lhs.suffix, rhs.suffix, span)?;
+            let apply = |op: &str, ops: Ops| apply(&lhs, &rhs, suffix, span, op, ops).map(Int);
+            let nonzero = |op: &str| match rhs.magnitude {
+                0 => Err(syn::Error::new(span, format!("attempt to {} by zero", op))),
+                _ => Ok(()),
+            };
+            match op {
+                BinOp::Add(_) => apply("add", (i128::checked_add, u128::checked_add)),
+                BinOp::Sub(_) => apply("subtract", (i128::checked_sub, u128::checked_sub)),
+                BinOp::Mul(_) => apply("multiply", (i128::checked_mul, u128::checked_mul)),
+                BinOp::Div(_) => {
+                    nonzero("divide").and_then(|_| apply("divide", (i128::checked_div, u128::checked_div)))
+                }
+                BinOp::Rem(_) => nonzero("calculate the remainder")
+                    .and_then(|_| apply("calculate the remainder", (i128::checked_rem, u128::checked_rem))),
+                BinOp::BitAnd(_) => apply("and", (|a, b| Some(a & b), |a, b| Some(a & b))),
+                BinOp::BitOr(_) => apply("or", (|a, b| Some(a | b), |a, b| Some(a | b))),
+                BinOp::BitXor(_) => apply("xor", (|a, b| Some(a ^ b), |a, b| Some(a ^ b))),
+                op => compare(op, lhs.cmp_value(&rhs), suffix.unwrap_or("integer"), span),
+            }
+        }
+        (op, Bool(lhs), Bool(rhs)) => match op {
+            BinOp::And(_) | BinOp::BitAnd(_) => Ok(Bool(lhs && rhs)),
+            BinOp::Or(_) | BinOp::BitOr(_) => Ok(Bool(lhs || rhs)),
+            BinOp::BitXor(_) => Ok(Bool(lhs ^ rhs)),
+            op => compare(op, lhs.cmp(&rhs), "bool", span),
+        },
+        (op, Char(lhs), Char(rhs)) => compare(op, lhs.cmp(&rhs), "char", span),
+        (op, Str(lhs), Str(rhs)) => compare(op, lhs.cmp(&rhs), "&str", span),
+        (_, lhs, rhs) => Err(syn::Error::new(
+            span,
+            format!(
+                "cannot apply this operator to `{}` and `{}`",
+                lhs.type_name(),
+                rhs.type_name()
+            ),
+        )),
+    }
+}
+
+fn compare(op: &BinOp, ordering: std::cmp::Ordering, type_name: &str, span: Span) -> syn::Result<Value> {
+    Ok(Value::Bool(match op {
+        BinOp::Eq(_) => ordering.is_eq(),
+        BinOp::Ne(_) => ordering.is_ne(),
+        BinOp::Lt(_) => ordering.is_lt(),
+        BinOp::Le(_) => ordering.is_le(),
+        BinOp::Gt(_) => ordering.is_gt(),
+        BinOp::Ge(_) => ordering.is_ge(),
+        _ => {
+            return Err(syn::Error::new(
+                span,
+                format!("cannot apply this operator to `{}` and `{}`", type_name, type_name),
+            ))
+        }
+    }))
+}
+
+fn eval_cast(value: Value, target: Option<&str>, span: Span) -> syn::Result<Value> {
+    let target = match target {
+        Some("char") => {
+            return match value {
+                Value::Char(c) => Ok(Value::Char(c)),
+                // Unsuffixed integers are inferred to be `u8`, as in Rust
+                Value::Int(int) if matches!(int.suffix, None | Some("u8")) => match int.fits("u8") {
+                    true => Ok(Value::Char(char::from(int.magnitude as u8))),
+                    false => Err(int.out_of_range(Some("u8"))),
+                },
+                _ => Err(syn::Error::new(span, "only `u8` can be cast to `char`")),
+            }
+        }
+        Some("bool") => {
+            return match value {
+                Value::Bool(b) => Ok(Value::Bool(b)),
+                _ => Err(syn::Error::new(span, "cannot cast to `bool`")),
+            }
+        }
+        Some(target) => int_suffix(target, span)
+            .ok()
+            .flatten()
+            .ok_or_else(|| syn::Error::new(span, format!("cannot cast to `{}`", target)))?,
+        None => return Err(syn::Error::new(span, "expected a cast to an integer type, `char` or `bool`")),
+    };
+
+    let bits = match value {
+        Value::Int(int) => to_bits(&int),
+        Value::Bool(b) => u128::from(b),
+        Value::Char(c) => u128::from(c),
+        Value::Str(_) => return Err(syn::Error::new(span, "cannot cast `&str` to an integer")),
+    };
+    Ok(Value::Int(from_bits(bits, target, span)))
+}
+
+super::impl_std_cps!(
+    fn eval_const(tokens: proc_macro2::TokenStream) -> syn::Result<proc_macro2::TokenTree> {
+        let span = tokens.span();
+        let expr: syn::Expr = syn::parse2(tokens)?;
+        eval(&expr)?.into_token(span)
+    }
+);
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use quote::quote;
+
+    fn evaluate(tokens: proc_macro2::TokenStream) -> String {
+        let expr: Expr = syn::parse2(tokens).expect("test expression did not parse");
+        match eval(&expr).and_then(|value| value.into_token(Span::call_site())) {
+            Ok(tt) => tt.to_string(),
+            Err(e) => e.to_string(),
+        }
+    }
+
+    #[test]
+    fn integers() {
+        assert_eq!(evaluate(quote! { 1 + 2 * (7 - 3) / 2 % 3 }), "2");
+        assert_eq!(evaluate(quote! { (1u8 << 3) | 0x3 }), "11u8");
+        assert_eq!(evaluate(quote! { -5 as u8 }), "251u8");
+        assert_eq!(evaluate(quote! { 300 as i8 }), "44i8");
+        assert_eq!(evaluate(quote! { !0u8 }), "255u8");
+        assert_eq!(evaluate(quote! { 'a' as u32 + 1 }), "98u32");
+        assert_eq!(evaluate(quote! { 1 << 31 }), "2147483648");
+        assert_eq!(evaluate(quote! { 1i32 << 31 }), "-2147483648i32");
+        assert_eq!(evaluate(quote! { 1i64 << 40 }), "1099511627776i64");
+        assert_eq!(evaluate(quote! { -8 >> 1 }), "-4");
+        assert_eq!(evaluate(quote! { !0 }), "-1");
+        assert_eq!(evaluate(quote! { -128i8 / 2 }), "-64i8");
+        assert_eq!(evaluate(quote! { 2147483647 + 1 }), "2147483648");
+    }
+
+    #[test]
+    fn large_integers() {
+        assert_eq!(
+            evaluate(quote! { 340282366920938463463374607431768211455u128 - 1 }),
+            "340282366920938463463374607431768211454u128"
+        );
+        assert_eq!(evaluate(quote! { -1 as u128 }), "340282366920938463463374607431768211455u128");
+        assert_eq!(evaluate(quote! { !0u128 >> 127 }), "1u128");
+        assert_eq!(
+            evaluate(quote! { -(-170141183460469231731687303715884105728) }),
+            "170141183460469231731687303715884105728"
+        );
+    }
+
+    #[test]
+    fn comparisons() {
+        assert_eq!(evaluate(quote! { 1 < 2 && !(3 >= 4) }), "true");
+        assert_eq!(evaluate(quote! { "abc" < "abd" }), "true");
+        assert_eq!(evaluate(quote! { 'a' == 'b' || false }), "false");
+        assert_eq!(evaluate(quote! { 97u8 as char }), "'a'");
+        assert_eq!(evaluate(quote! { 97 as char }), "'a'");
+    }
+
+    #[test]
+    fn errors() {
+        assert_eq!(evaluate(quote! { 200u8 + 100 }), "attempt to add with overflow");
+        assert_eq!(evaluate(quote! { 1 / (2 - 2) }), "attempt to divide by zero");
+        assert_eq!(
+            evaluate(quote! { 1u8 + 1u16 }),
+            "mismatched integer types, expected `u8` but found `u16`"
+        );
+        assert_eq!(evaluate(quote! { -1u8 }), "cannot negate an unsigned integer");
+        assert_eq!(evaluate(quote! { "a" + "b" }), "cannot apply this operator to `&str` and `&str`");
+        assert_eq!(evaluate(quote! { 1 + true }), "cannot apply this operator to `integer` and `bool`");
+        assert_eq!(evaluate(quote! { 1i32 << 32 }), "attempt to shift with overflow");
+        assert_eq!(evaluate(quote! { 1 << 128 }), "attempt to shift with overflow");
+        assert_eq!(evaluate(quote! { 3 << 127 }), "attempt to shift left with overflow");
+        assert_eq!(evaluate(quote! { -(-128i8) }), "attempt to negate with overflow");
+        assert_eq!(
+            evaluate(quote! { -(-170141183460469231731687303715884105728i128) }),
+            "attempt to negate with overflow"
+        );
+        assert_eq!(
+            evaluate(quote! { -340282366920938463463374607431768211455 }),
+            "`-340282366920938463463374607431768211455` is too large"
+        );
+        assert_eq!(
+            evaluate(quote! { 340282366920938463463374607431768211455u128 + 1 }),
+            "attempt to add with overflow"
+        );
+        assert_eq!(evaluate(quote! { 300 as char }), "`300` is out of range for `u8`");
+        assert_eq!(evaluate(quote! { 300u8 }), "`300` is out of range for `u8`");
+        assert_eq!(evaluate(quote! { false && 1 }), "cannot apply this operator to `bool` and `integer`");
+        assert_eq!(evaluate(quote! { true || "a" }), "cannot apply this operator to `bool` and `&str`");
+        assert_eq!(
+            evaluate(quote! { f(1) }),
+            "unsupported expression, expected literals combined with operators, parentheses and casts"
+        );
+    }
+}
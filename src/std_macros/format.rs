@@ -0,0 +1,489 @@
+use super::literals::{int_width, is_float_suffix, FloatLit, IntLit, StrLit};
+use crate::cps_macro::flatten_none_groups;
+use proc_macro2::{Literal, Span, TokenStream};
+use std::fmt::{Debug, Display, LowerExp, UpperExp};
+use std::iter::Peekable;
+use std::str::Chars;
+use syn::ext::IdentExt;
+use syn::parse::{Parse, ParseStream};
+use syn::{Ident, Token};
+
+/// A value that can be formatted.
+enum Value {
+    Int(IntLit),
+    Float(f64, bool),
+    Str(String),
+    Char(char),
+    Bool(bool),
+}
+
+impl Parse for Value {
+    fn parse(input: ParseStream) -> syn::Result<Self> {
+        // Identifiers are formatted as their text, so that they can be used to build other identifiers
+        if input.peek(Ident::peek_any) {
+            let ident = Ident::parse_any(input)?;
+            return Ok(match ident.to_string().as_str() {
+                "true" => Self::Bool(true),
+                "false" => Self::Bool(false),
+                _ => Self::Str(ident.unraw().to_string()),
+            });
+        }
+
+        let negated = input.parse::<Option<Token![-]>>()?.is_some();
+        let literal: Literal = input
+            .parse()
+            .map_err(|_| input.error("expected a literal or identifier to format"))?;
+        let span = literal.span();
+        match litrs::Literal::from(&literal) {
+            litrs::Literal::Integer(int) if !is_float_suffix(int.suffix()) => {
+                Ok(Self::Int(IntLit::from_literal(negated, &literal)?))
+            }
+            litrs::Literal::Integer(_) | litrs::Literal::Float(_) => {
+                let float = FloatLit::from_literal(negated, &literal)?;
+                Ok(Self::Float(float.value, float.suffix == "f32"))
+            }
+            litrs::Literal::String(s) if !negated => Ok(Self::Str(s.value().to_owned())),
+            litrs::Literal::Char(c) if !negated => Ok(Self::Char(c.value())),
+            _ => Err(syn::Error::new(span, "expected a literal or identifier to format")),
+        }
+    }
+}
+
+struct Arg {
+    name: Option<String>,
+    value: Value,
+    span: Span,
+    used: bool,
+}
+
+struct FormatInput {
+    format: StrLit,
+    args: Vec<Arg>,
+}
+
+impl Parse for FormatInput {
+    fn parse(input: ParseStream) -> syn::Result<Self> {
+        let format: StrLit = input.parse()?;
+        let mut args: Vec<Arg> = Vec::new();
+        while !input.is_empty() {
+            input.parse::<Token![,]>()?;
+            if input.is_empty() {
+                break;
+            }
+
+            let span = input.span();
+            let name = if input.peek(Ident::peek_any) && input.peek2(Token![=]) && !input.peek2(Token![==]) {
+                let name = Ident::parse_any(input)?.unraw().to_string();
+                input.parse::<Token![=]>()?;
+                if args.iter().any(|arg| arg.name.as_ref() == Some(&name)) {
+                    return Err(syn::Error::new(span, format!("duplicate argument named `{}`", name)));
+                }
+                Some(name)
+            } else {
+                if args.iter().any(|arg| arg.name.is_some()) {
+                    return Err(syn::Error::new(span, "positional arguments cannot follow named arguments"));
+                }
+                None
+            };
+            let value = input.parse()?;
+            args.push(Arg {
+                name,
+                value,
+                span,
+                used: false,
+            });
+        }
+
+        Ok(Self { format, args })
+    }
+}
+
+/// Which argument a placeholder refers to.
+enum ArgRef {
+    Next,
+    Index(usize),
+    Name(String),
+}
+
+/// A width or precision, either given directly or taken from an argument.
+enum Count {
+    Value(usize),
+    Arg(ArgRef),
+}
+
+#[derive(Clone, Copy, PartialEq)]
+enum Align {
+    Left,
+    Center,
+    Right,
+}
+
+/// The part of a placeholder after the `:`, like `>+#08.3x`.
+struct Spec {
+    fill: char,
+    align: Option<Align>,
+    plus: bool,
+    alternate: bool,
+    zero: bool,
+    width: Option<Count>,
+    precision: Option<Count>,
+    ty: String,
+}
+
+fn parse_align(c: char) -> Option<Align> {
+    match c {
+        '<' => Some(Align::Left),
+        '^' => Some(Align::Center),
+        '>' => Some(Align::Right),
+        _ => None,
+    }
+}
+
+fn parse_arg_ref(text: &str) -> Result<ArgRef, String> {
+    let text = text.trim();
+    if text.is_empty() {
+        Ok(ArgRef::Next)
+    } else if let Ok(index) = text.parse() {
+        Ok(ArgRef::Index(index))
+    } else if syn::parse_str::<Ident>(text).is_ok() {
+        Ok(ArgRef::Name(text.to_owned()))
+    } else {
+        Err(format!("invalid argument name `{}`", text))
+    }
+}
+
+/// Parses a count like `5`, `1$` or `name$` at the start of some chars, returning it and the number of chars used.
+fn parse_count(chars: &[char]) -> Option<(Count, usize)> {
+    let len = chars
+        .iter()
+        .take_while(|c| c.is_ascii_alphanumeric() || **c == '_')
+        .count();
+    if chars.get(len) == Some(&'$') {
+        let text: String = chars[..len].iter().collect();
+        return parse_arg_ref(&text).ok().map(|arg| (Count::Arg(arg), len + 1));
+    }
+
+    let len = chars.iter().take_while(|c| c.is_ascii_digit()).count();
+    let text: String = chars[..len].iter().collect();
+    text.parse().ok().map(|value| (Count::Value(value), len))
+}
+
+fn parse_spec(text: &str) -> Result<Spec, String> {
+    let chars: Vec<char> = text.chars().collect();
+    let mut spec = Spec {
+        fill: ' ',
+        align: None,
+        plus: false,
+        alternate: false,
+        zero: false,
+        width: None,
+        precision: None,
+        ty: String::new(),
+    };
+
+    let mut i = 0;
+    if let Some(align) = chars.get(1).copied().and_then(parse_align) {
+        spec.fill = chars[0];
+        spec.align = Some(align);
+        i = 2;
+    } else if let Some(align) = chars.first().copied().and_then(parse_align) {
+        spec.align = Some(align);
+        i = 1;
+    }
+    match chars.get(i) {
+        Some('+') => {
+            spec.plus = true;
+            i += 1;
+        }
+        Some('-') => i += 1,
+        _ => {}
+    }
+    if chars.get(i) == Some(&'#') {
+        spec.alternate = true;
+        i += 1;
+    }
+    if chars.get(i) == Some(&'0') && chars.get(i + 1) != Some(&'$') {
+        spec.zero = true;
+        i += 1;
+    }
+    if let Some((width, len)) = parse_count(&chars[i..]) {
+        spec.width = Some(width);
+        i += len;
+    }
+    if chars.get(i) == Some(&'.') {
+        i += 1;
+        if chars.get(i) == Some(&'*') {
+            spec.precision = Some(Count::Arg(ArgRef::Next));
+            i += 1;
+        } else {
+            let (precision, len) = parse_count(&chars[i..]).ok_or("expected a precision after `.`")?;
+            spec.precision = Some(precision);
+            i += len;
+        }
+    }
+    spec.ty = chars[i..].iter().collect();
+
+    match spec.ty.as_str() {
+        "" | "?" | "x" | "X" | "b" | "o" | "e" | "E" => Ok(spec),
+        ty => Err(format!("unknown format trait `{}`", ty)),
+    }
+}
+
+fn format_float<T: Display + Debug + LowerExp + UpperExp>(value: T, ty: &str, precision: Option<usize>) -> String {
+    match (ty, precision) {
+        ("?", None) => format!("{:?}", value),
+        ("?", Some(p)) => format!("{:.*?}", p, value),
+        ("e", None) => format!("{:e}", value),
+        ("e", Some(p)) => format!("{:.*e}", p, value),
+        ("E", None) => format!("{:E}", value),
+        ("E", Some(p)) => format!("{:.*E}", p, value),
+        (_, None) => format!("{}", value),
+        (_, Some(p)) => format!("{:.*}", p, value),
+    }
+}
+
+/// Formats a value, returning its sign and prefix, its digits or text, and whether it is a number.
+fn format_value(value: &Value, spec: &Spec, precision: Option<usize>) -> Result<(String, String, bool), String> {
+    let ty = spec.ty.as_str();
+    let sign = |negative: bool| match (negative, spec.plus) {
+        (true, _) => "-",
+        (false, true) => "+",
+        (false, false) => "",
+    };
+    match value {
+        Value::Int(int) => {
+            let magnitude = int.magnitude;
+            let (bits, _) = int_width(int.suffix.unwrap_or("i32"));
+            let value = if int.negative { magnitude.wrapping_neg() } else { magnitude };
+            let twos_complement = if bits == 128 { value } else { value & ((1u128 << bits) - 1) };
+            let prefix = |prefix: &str| if spec.alternate { prefix.to_owned() } else { String::new() };
+            Ok(match ty {
+                "x" => (sign(false).to_owned() + &prefix("0x"), format!("{:x}", twos_complement), true),
+                "X" => (sign(false).to_owned() + &prefix("0x"), format!("{:X}", twos_complement), true),
+                "b" => (sign(false).to_owned() + &prefix("0b"), format!("{:b}", twos_complement), true),
+                "o" => (sign(false).to_owned() + &prefix("0o"), format!("{:o}", twos_complement), true),
+                "e" | "E" => (sign(int.negative).to_owned(), format_float(magnitude, ty, precision), true),
+                _ => (sign(int.negative).to_owned(), magnitude.to_string(), true),
+            })
+        }
+        Value::Float(..) if matches!(ty, "x" | "X" | "b" | "o") => {
+            Err(format!("`{}` formatting is only supported for integers", ty))
+        }
+        Value::Float(value, is_f32) => {
+            let text = if *is_f32 {
+                format_float((*value as f32).abs(), ty, precision)
+            } else {
+                format_float(value.abs(), ty, precision)
+            };
+            Ok((sign(value.is_sign_negative()).to_owned(), text, true))
+        }
+        _ if !ty.is_empty() && ty != "?" => Err(format!("`{}` formatting is only supported for numbers", ty)),
+        Value::Str(s) if ty == "?" => Ok((String::new(), format!("{:?}", s), false)),
+        Value::Char(c) if ty == "?" => Ok((String::new(), format!("{:?}", c), false)),
+        _ => {
+            let text = match value {
+                Value::Str(s) => s.clone(),
+                Value::Char(c) => c.to_string(),
+                Value::Bool(b) => b.to_string(),
+                Value::Int(_) | Value::Float(..) => unreachable!("numbers are formatted above"),
+            };
+            Ok((String::new(), truncate(text, precision), false))
+        }
+    }
+}
+
+fn truncate(text: String, precision: Option<usize>) -> String {
+    match precision {
+        Some(precision) => text.chars().take(precision).collect(),
+        None => text,
+    }
+}
+
+/// Pads formatted text to a width, using zeros after the sign for numbers with the `0` flag.
+fn pad(prefix: String, body: String, numeric: bool, spec: &Spec, width: Option<usize>) -> String {
+    let len = prefix.chars().count() + body.chars().count();
+    let padding = width.map(|width| width.saturating_sub(len)).unwrap_or(0);
+    if spec.zero && numeric {
+        return prefix + &"0".repeat(padding) + &body;
+    }
+
+    let default_align = if numeric { Align::Right } else { Align::Left };
+    let (before, after) = match spec.align.unwrap_or(default_align) {
+        Align::Left => (0, padding),
+        Align::Center => (padding / 2, padding - padding / 2),
+        Align::Right => (padding, 0),
+    };
+    let fill = |n: usize| spec.fill.to_string().repeat(n);
+    fill(before) + &prefix + &body + &fill(after)
+}
+
+struct Formatter {
+    args: Vec<Arg>,
+    next: usize,
+}
+
+impl Formatter {
+    fn resolve(&mut self, arg: &ArgRef) -> Result<usize, String> {
+        let index = match arg {
+            ArgRef::Next => {
+                self.next += 1;
+                self.next - 1
+            }
+            ArgRef::Index(index) => *index,
+            ArgRef::Name(name) => self
+                .args
+                .iter()
+                .position(|arg| arg.name.as_ref() == Some(name))
+                .ok_or_else(|| format!("there is no argument named `{}`", name))?,
+        };
+        match self.args.get_mut(index) {
+            Some(arg) => {
+                arg.used = true;
+                Ok(index)
+            }
+            None => Err(format!(
+                "invalid reference to positional argument {}, there are {} arguments",
+                index,
+                self.args.len()
+            )),
+        }
+    }
+
+    fn count(&mut self, count: &Option<Count>) -> Result<Option<usize>, String> {
+        match count {
+            None => Ok(None),
+            Some(Count::Value(value)) => Ok(Some(*value)),
+            Some(Count::Arg(arg)) => {
+                let index = self.resolve(arg)?;
+                match &self.args[index].value {
+                    Value::Int(int) => int
+                        .to_u128()
+                        .and_then(|value| usize::try_from(value).ok())
+                        .map(Some)
+                        .ok_or_else(|| "expected a non-negative width or precision".to_owned()),
+                    _ => Err("expected an integer argument for a width or precision".to_owned()),
+                }
+            }
+        }
+    }
+
+    fn placeholder(&mut self, text: &str) -> Result<String, String> {
+        let (arg, spec) = match text.split_once(':') {
+            Some((arg, spec)) => (arg, parse_spec(spec)?),
+            None => (text, parse_spec("")?),
+        };
+        let arg = parse_arg_ref(arg)?;
+
+        // As in std, a precision of `.*` takes the next positional argument before the value does
+        let precision = self.count(&spec.precision)?;
+        let index = self.resolve(&arg)?;
+        let width = self.count(&spec.width)?;
+
+        let (prefix, body, numeric) = format_value(&self.args[index].value, &spec, precision)?;
+        Ok(pad(prefix, body, numeric, &spec, width))
+    }
+
+    fn format(&mut self, chars: &mut Peekable<Chars>) -> Result<String, String> {
+        let mut output = String::new();
+        while let Some(c) = chars.next() {
+            match c {
+                '{' if chars.peek() == Some(&'{') => {
+                    chars.next();
+                    output.push('{');
+                }
+                '}' if chars.peek() == Some(&'}') => {
+                    chars.next();
+                    output.push('}');
+                }
+                '{' => {
+                    let mut text = String::new();
+                    loop {
+                        match chars.next() {
+                            Some('}') => break,
+                            Some('{') | None => return Err("unterminated placeholder, expected `}`".to_owned()),
+                            Some(c) => text.push(c),
+                        }
+                    }
+                    output += &self.placeholder(&text)?;
+                }
+                '}' => return Err("unmatched `}`, use `}}` to include a `}`".to_owned()),
+                c => output.push(c),
+            }
+        }
+        Ok(output)
+    }
+}
+
+fn format_tokens(tokens: TokenStream) -> syn::Result<String> {
+    let input: FormatInput = syn::parse2(flatten_none_groups(tokens))?;
+    let mut formatter = Formatter {
+        args: input.args,
+        next: 0,
+    };
+    let output = formatter
+        .format(&mut input.format.value.chars().peekable())
+        .map_err(|e| syn::Error::new(input.format.span, e))?;
+
+    if let Some(unused) = formatter.args.iter().find(|arg| !arg.used) {
+        return Err(syn::Error::new(unused.span, "argument never used"));
+    }
+    Ok(output)
+}
+
+super::impl_std_cps!(
+    fn format(tokens: proc_macro2::TokenStream) -> syn::Result<proc_macro2::Literal> {
+        Ok(Literal::string(&format_tokens(tokens)?))
+    }
+);
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use quote::quote;
+
+    fn result(tokens: TokenStream) -> Result<String, String> {
+        format_tokens(tokens).map_err(|e| e.to_string())
+    }
+
+    #[test]
+    fn arguments() {
+        assert_eq!(result(quote! { "{name}_{}", x, name = get }), Ok("get_x".to_owned()));
+        assert_eq!(result(quote! { "{1}{0}{}", 'a', "b" }), Ok("baa".to_owned()));
+        assert_eq!(result(quote! { "{{{}}}", true }), Ok("{true}".to_owned()));
+        assert_eq!(result(quote! { "{:?} {:?}", "a\"b", 'c' }), Ok("\"a\\\"b\" 'c'".to_owned()));
+    }
+
+    #[test]
+    fn specs() {
+        assert_eq!(result(quote! { "[{:>5}|{:<5}|{:^5}]", 1, "a", 'b' }), Ok("[    1|a    |  b  ]".to_owned()));
+        assert_eq!(result(quote! { "{:*^7.2}", "abcd" }), Ok("**ab***".to_owned()));
+        assert_eq!(result(quote! { "{:+05}", -3 }), Ok("-0003".to_owned()));
+        assert_eq!(result(quote! { "{:+}", 3 }), Ok("+3".to_owned()));
+        assert_eq!(result(quote! { "{:#06x} {:X} {:b} {:#o}", 255, 255, 5, 8 }), Ok("0x00ff FF 101 0o10".to_owned()));
+        assert_eq!(result(quote! { "{:x} {:x}", -1i8, -1 }), Ok("ff ffffffff".to_owned()));
+        assert_eq!(result(quote! { "{:.2} {:?} {:e}", 1.005, 1.0, 1234.5 }), Ok("1.00 1.0 1.2345e3".to_owned()));
+        assert_eq!(result(quote! { "{:3$}|{:.*}|{:w$}", 7, 2, 1.5, 3, w = 4 }), Ok("  7|1.50|   3".to_owned()));
+        assert_eq!(result(quote! { "{}", 0.1f32 }), Ok("0.1".to_owned()));
+        assert_eq!(result(quote! { "{} {:?} {:.1}", 1f32, 2f64, -3f64 }), Ok("1 2.0 -3.0".to_owned()));
+        assert_eq!(result(quote! { "{:4x}|{:<#4b}", 10, 1 }), Ok("   a|0b1 ".to_owned()));
+    }
+
+    #[test]
+    fn errors() {
+        assert_eq!(
+            result(quote! { "{}" }),
+            Err("invalid reference to positional argument 0, there are 0 arguments".to_owned())
+        );
+        assert_eq!(result(quote! { "{}", 1, 2 }), Err("argument never used".to_owned()));
+        assert_eq!(result(quote! { "{x}", y = 1 }), Err("there is no argument named `x`".to_owned()));
+        assert_eq!(result(quote! { "{:x}", "a" }), Err("`x` formatting is only supported for numbers".to_owned()));
+        assert_eq!(result(quote! { "{:q}", 1 }), Err("unknown format trait `q`".to_owned()));
+        assert_eq!(result(quote! { "{", 1 }), Err("unterminated placeholder, expected `}`".to_owned()));
+        assert_eq!(result(quote! { "}" }), Err("unmatched `}`, use `}}` to include a `}`".to_owned()));
+        assert_eq!(
+            result(quote! { "{a}", a = 1, 2 }),
+            Err("positional arguments cannot follow named arguments".to_owned())
+        );
+        assert_eq!(result(quote! { "{:x}", 1f32 }), Err("`x` formatting is only supported for integers".to_owned()));
+    }
+}
@@ -0,0 +1,131 @@
+use super::literals::StrLit;
+use super::options::Options;
+use crate::cps_macro::flatten_none_groups;
+use proc_macro2::{Ident, Span, TokenStream, TokenTree};
+use syn::parse::{Parse, ParseStream};
+use syn::punctuated::Punctuated;
+use syn::Token;
+
+struct IdentInput {
+    raw: bool,
+    span: Option<Span>,
+    parts: Punctuated<TokenTree, Token![,]>,
+}
+
+impl Parse for IdentInput {
+    fn parse(input: ParseStream) -> syn::Result<Self> {
+        let options = Options::parse(input, &["raw", "span"])?;
+        let parts = Punctuated::parse_terminated(input)?;
+        if parts.is_empty() {
+            return Err(input.error("expected at least one part to build an identifier from"));
+        }
+
+        Ok(Self {
+            raw: options.flag("raw")?,
+            span: options.span()?,
+            parts,
+        })
+    }
+}
+
+/// The text that a part contributes to an identifier.
+fn part_text(part: &TokenTree) -> syn::Result<String> {
+    let err = || syn::Error::new(part.span(), "expected an identifier, string, character or integer");
+    match part {
+        TokenTree::Ident(i) => {
+            let i = i.to_string();
+            Ok(i.strip_prefix("r#").map(str::to_owned).unwrap_or(i))
+        }
+        TokenTree::Literal(l) => match litrs::Literal::from(l) {
+            litrs::Literal::Char(c) => Ok(c.value().to_string()),
+            litrs::Literal::Integer(i) => Ok(i.raw_input()[..i.raw_input().len() - i.suffix().len()].to_owned()),
+            _ => StrLit::from_literal(l).map(|s| s.value).map_err(|_| err()),
+        },
+        _ => Err(err()),
+    }
+}
+
+/// Checks that some text is a single identifier token, which may or may not be a keyword.
+fn is_ident_token(text: &str) -> bool {
+    let tokens: Vec<_> = match text.parse::<TokenStream>() {
+        Ok(tokens) => tokens.into_iter().collect(),
+        Err(_) => return false,
+    };
+    matches!(tokens.as_slice(), [TokenTree::Ident(i)] if i == text) && !text.starts_with("r#")
+}
+
+/// Whether some text is a keyword, or otherwise can't be used as a non-raw identifier.
+pub fn is_keyword(text: &str) -> bool {
+    syn::parse_str::<syn::Ident>(text).is_err()
+}
+
+/// Creates an identifier, checking that it is valid.
+pub fn new_ident(text: &str, span: Span, raw: bool) -> syn::Result<Ident> {
+    if !is_ident_token(text) || text == "_" {
+        return Err(syn::Error::new(span, format!("`{}` is not a valid identifier", text)));
+    }
+
+    if raw {
+        if matches!(text, "crate" | "self" | "super" | "Self") {
+            return Err(syn::Error::new(span, format!("`{}` cannot be a raw identifier", text)));
+        }
+        Ok(Ident::new_raw(text, span))
+    } else if is_keyword(text) {
+        Err(syn::Error::new(
+            span,
+            format!("`{}` is a keyword, add `#[raw]` to create `r#{}` instead", text, text),
+        ))
+    } else {
+        Ok(Ident::new(text, span))
+    }
+}
+
+fn build_ident(input: IdentInput) -> syn::Result<Ident> {
+    let text = input.parts.iter().map(part_text).collect::<syn::Result<String>>()?;
+    new_ident(&text, input.span.unwrap_or_else(Span::call_site), input.raw)
+}
+
+super::impl_std_cps!(
+    fn ident(tokens: proc_macro2::TokenStream) -> syn::Result<proc_macro2::Ident> {
+        build_ident(syn::parse2(flatten_none_groups(tokens))?)
+    }
+);
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use quote::quote;
+
+    fn build(tokens: TokenStream) -> Result<String, String> {
+        syn::parse2(tokens)
+            .and_then(build_ident)
+            .map(|ident| ident.to_string())
+            .map_err(|e| e.to_string())
+    }
+
+    #[test]
+    fn valid_idents() {
+        assert_eq!(build(quote! { a, "b", 'c', 1 }), Ok("abc1".to_owned()));
+        assert_eq!(build(quote! { r#type, _name }), Ok("type_name".to_owned()));
+        assert_eq!(build(quote! { #[raw] "fn" }), Ok("r#fn".to_owned()));
+    }
+
+    #[test]
+    fn invalid_idents() {
+        assert_eq!(build(quote! { "a b" }), Err("`a b` is not a valid identifier".to_owned()));
+        assert_eq!(build(quote! { 1, a }), Err("`1a` is not a valid identifier".to_owned()));
+        assert_eq!(
+            build(quote! { "fn" }),
+            Err("`fn` is a keyword, add `#[raw]` to create `r#fn` instead".to_owned())
+        );
+        assert_eq!(build(quote! { #[raw] self }), Err("`self` cannot be a raw identifier".to_owned()));
+        assert_eq!(
+            build(quote! { a, 1.5 }),
+            Err("expected an identifier, string, character or integer".to_owned())
+        );
+        assert_eq!(
+            build(quote! { #[unknown] a }),
+            Err("unknown option, expected one of `#[raw]`, `#[span]`".to_owned())
+        );
+    }
+}
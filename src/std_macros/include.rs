@@ -1,25 +1,41 @@
-use std::fs;
+use super::lex_errors::lex_error;
+use super::paths::IncludePath;
+use super::unstringify::respan;
+use proc_macro2::Span;
 
-use proc_macro2::Literal;
+fn do_include(tokens: proc_macro2::TokenStream) -> syn::Result<proc_macro2::TokenStream> {
+    let path: IncludePath = syn::parse2(tokens)?;
+    let (file, contents) = path.read_to_string()?;
+    let included: proc_macro2::TokenStream = contents
+        .parse()
+        .map_err(|e| lex_error(&file, &contents, e, path.path.span))?;
 
-fn do_include(tokens: proc_macro2::TokenStream) -> proc_macro2::TokenStream {
-    let path: Literal = match syn::parse2(tokens) {
-        Err(e) => panic!("error parsing include input: {}", e),
-        Ok(v) => v,
-    };
-
-    let path = match litrs::Literal::from(path.clone()) {
-        litrs::Literal::String(s) => s.value().to_string(),
-        _ => panic!("path {} was not a string", path),
-    };
-
-    let contents = fs::read_to_string(&path).unwrap_or_else(|_| panic!("include file {path} was unreadable - note that paths must be relative to the project's manifest file"));
-
-    contents.parse().expect("could not parse included file")
+    // Tokens can't be given spans in the included file, so errors in them point to the path that included them. They
+    // keep call-site hygiene, as they would if they had been written where the macro was invoked
+    Ok(respan(included, Span::call_site().located_at(path.path.span)))
 }
 
 super::impl_std_cps!(
-    fn include(tokens: proc_macro2::TokenStream) -> proc_macro2::TokenStream {
+    fn include(tokens: proc_macro2::TokenStream) -> syn::Result<proc_macro2::TokenStream> {
         include::do_include(tokens)
     }
 );
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use quote::quote;
+
+    #[test]
+    fn lex_error_location() {
+        let error = do_include(quote! { #[manifest_dir] "tests/unbalanced_file.txt" })
+            .err()
+            .map(|e| e.to_string())
+            .unwrap_or_default();
+        let file = std::path::Path::new(env!("CARGO_MANIFEST_DIR")).join("tests/unbalanced_file.txt");
+        assert_eq!(
+            error,
+            format!("could not lex `{}` at line 3, column 1: mismatched closing delimiter: `}}`", file.display())
+        );
+    }
+}
@@ -0,0 +1,34 @@
+use super::paths::IncludePath;
+use proc_macro2::Literal;
+use quote::quote;
+
+fn read_bytes(tokens: proc_macro2::TokenStream) -> syn::Result<(Vec<u8>, proc_macro2::Span)> {
+    let path: IncludePath = syn::parse2(tokens)?;
+    let (_, contents) = path.read()?;
+    Ok((contents, path.path.span))
+}
+
+fn do_include_bytes(tokens: proc_macro2::TokenStream) -> syn::Result<Literal> {
+    let (contents, span) = read_bytes(tokens)?;
+    let mut literal = Literal::byte_string(&contents);
+    literal.set_span(span);
+    Ok(literal)
+}
+
+fn do_include_bytes_list(tokens: proc_macro2::TokenStream) -> syn::Result<proc_macro2::TokenStream> {
+    let (contents, _) = read_bytes(tokens)?;
+    let bytes = contents.into_iter().map(Literal::u8_suffixed);
+    Ok(quote! { #(#bytes),* })
+}
+
+super::impl_std_cps!(
+    fn include_bytes(tokens: proc_macro2::TokenStream) -> syn::Result<proc_macro2::Literal> {
+        do_include_bytes(tokens)
+    }
+);
+
+super::impl_std_cps!(
+    fn include_bytes_list(tokens: proc_macro2::TokenStream) -> syn::Result<proc_macro2::TokenStream> {
+        do_include_bytes_list(tokens)
+    }
+);
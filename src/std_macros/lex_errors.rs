@@ -0,0 +1,190 @@
+use proc_macro2::{LexError, Span};
+use std::path::Path;
+
+/// A problem that stops some source text from being lexed into tokens, found at a line and column counted from 1,
+/// as in compiler diagnostics.
+struct LexProblem {
+    line: usize,
+    column: usize,
+    message: String,
+}
+
+fn problem(chars: &[char], index: usize, message: String) -> LexProblem {
+    let before = &chars[..index];
+    let line = before.iter().filter(|c| **c == '\n').count() + 1;
+    let column = before.iter().rev().take_while(|c| **c != '\n').count() + 1;
+    LexProblem { line, column, message }
+}
+
+fn is_word_char(c: char) -> bool {
+    c.is_alphanumeric() || c == '_'
+}
+
+fn is_punct(c: char) -> bool {
+    "=<>!~+-*/%^&|@.,;:#$?".contains(c)
+}
+
+/// Skips over a string's contents from after its opening quote, returning the index after its closing quote.
+fn skip_string(chars: &[char], mut i: usize, escapes: bool) -> Option<usize> {
+    while i < chars.len() {
+        match chars[i] {
+            '\\' if escapes => i += 2,
+            '"' => return Some(i + 1),
+            _ => i += 1,
+        }
+    }
+    None
+}
+
+/// Finds the first problem that would stop some source text from being lexed. This doesn't fully lex the text, but
+/// finds the mistakes that are likely to be made in files written by hand: unbalanced delimiters, unterminated
+/// strings, characters and comments, and characters that can't start a token.
+fn find_lex_problem(source: &str) -> Option<LexProblem> {
+    let chars: Vec<char> = source.chars().collect();
+    let at = |i: usize| chars.get(i).copied();
+    let mut open: Vec<(char, usize)> = Vec::new();
+    let mut i = 0;
+    while i < chars.len() {
+        let c = chars[i];
+        match c {
+            '/' if at(i + 1) == Some('/') => {
+                while i < chars.len() && chars[i] != '\n' {
+                    i += 1;
+                }
+            }
+            '/' if at(i + 1) == Some('*') => {
+                let start = i;
+                let mut depth = 0;
+                loop {
+                    match (at(i), at(i + 1)) {
+                        (None, _) => return Some(problem(&chars, start, "unterminated block comment".to_owned())),
+                        (Some('/'), Some('*')) => {
+                            depth += 1;
+                            i += 2;
+                        }
+                        (Some('*'), Some('/')) => {
+                            depth -= 1;
+                            i += 2;
+                            if depth == 0 {
+                                break;
+                            }
+                        }
+                        _ => i += 1,
+                    }
+                }
+            }
+            '(' | '[' | '{' => {
+                open.push((c, i));
+                i += 1;
+            }
+            ')' | ']' | '}' => {
+                let expected = match open.pop() {
+                    Some(('(', _)) => ')',
+                    Some(('[', _)) => ']',
+                    Some(_) => '}',
+                    None => return Some(problem(&chars, i, format!("unexpected closing delimiter: `{}`", c))),
+                };
+                if c != expected {
+                    return Some(problem(&chars, i, format!("mismatched closing delimiter: `{}`", c)));
+                }
+                i += 1;
+            }
+            '"' => match skip_string(&chars, i + 1, true) {
+                Some(end) => i = end,
+                None => return Some(problem(&chars, i, "unterminated double quote string".to_owned())),
+            },
+            '\'' => {
+                // Distinguish character literals from lifetimes and labels
+                if at(i + 1) == Some('\\') {
+                    // Step over the escaped character, which may itself be a quote or backslash
+                    let end = (i + 3..chars.len().min(i + 14)).find(|j| chars[*j] == '\'');
+                    match end {
+                        Some(end) => i = end + 1,
+                        None => return Some(problem(&chars, i, "unterminated character literal".to_owned())),
+                    }
+                } else if at(i + 2) == Some('\'') {
+                    i += 3;
+                } else {
+                    i += 1;
+                }
+            }
+            c if is_word_char(c) => {
+                let start = i;
+                while at(i).is_some_and(is_word_char) {
+                    i += 1;
+                }
+                let word: String = chars[start..i].iter().collect();
+                match (word.as_str(), at(i)) {
+                    ("b" | "c", Some('"')) => match skip_string(&chars, i + 1, true) {
+                        Some(end) => i = end,
+                        None => return Some(problem(&chars, start, "unterminated double quote string".to_owned())),
+                    },
+                    ("r" | "br" | "cr", Some('"' | '#')) => {
+                        let hashes = chars[i..].iter().take_while(|c| **c == '#').count();
+                        if at(i + hashes) != Some('"') {
+                            // A raw identifier, like `r#type`
+                            i += hashes;
+                            continue;
+                        }
+                        let closing: Vec<char> = std::iter::once('"').chain("#".repeat(hashes).chars()).collect();
+                        let end = (i + hashes + 1..chars.len()).find(|j| chars[*j..].starts_with(&closing));
+                        match end {
+                            Some(end) => i = end + closing.len(),
+                            None => return Some(problem(&chars, start, "unterminated raw string".to_owned())),
+                        }
+                    }
+                    _ => {}
+                }
+            }
+            c if c.is_whitespace() || is_punct(c) => i += 1,
+            c => return Some(problem(&chars, i, format!("unknown start of token: {}", c.escape_debug()))),
+        }
+    }
+
+    open.pop().map(|(c, i)| problem(&chars, i, format!("unclosed delimiter: `{}`", c)))
+}
+
+/// Describes a file that couldn't be lexed, giving the line and column of the problem where it can be found.
+pub fn lex_error(file: &Path, source: &str, error: LexError, span: Span) -> syn::Error {
+    let message = match find_lex_problem(source) {
+        Some(problem) => format!(
+            "could not lex `{}` at line {}, column {}: {}",
+            file.display(),
+            problem.line,
+            problem.column,
+            problem.message
+        ),
+        None => format!("could not lex `{}`: {}", file.display(), error),
+    };
+    syn::Error::new(span, message)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn find(source: &str) -> Option<(usize, usize, String)> {
+        find_lex_problem(source).map(|problem| (problem.line, problem.column, problem.message))
+    }
+
+    #[test]
+    fn valid_sources() {
+        assert_eq!(find("fn main() { let x = [1, 2]; }"), None);
+        assert_eq!(find("// (\n/* { /* nested */ */ \"(\" '(' b'[' r#\"}\"# br\"{\""), None);
+        assert_eq!(find("fn f<'a>(x: &'a str) -> char { '\\'' }"), None);
+        assert_eq!(find("let r#type = \"\\\"\";"), None);
+        assert_eq!(find("['\\\\', '\\x7f', '\\u{1F600}', b'\\\\']"), None);
+    }
+
+    #[test]
+    fn problems() {
+        assert_eq!(find("fn main() {\n    (1, 2\n}"), Some((3, 1, "mismatched closing delimiter: `}`".to_owned())));
+        assert_eq!(find("a\n  b {"), Some((2, 5, "unclosed delimiter: `{`".to_owned())));
+        assert_eq!(find("x)"), Some((1, 2, "unexpected closing delimiter: `)`".to_owned())));
+        assert_eq!(find("let s = \"abc;"), Some((1, 9, "unterminated double quote string".to_owned())));
+        assert_eq!(find("/* a"), Some((1, 1, "unterminated block comment".to_owned())));
+        assert_eq!(find("r##\"a\"#"), Some((1, 1, "unterminated raw string".to_owned())));
+        assert_eq!(find("a ` b"), Some((1, 3, "unknown start of token: `".to_owned())));
+        assert_eq!(find("x = '\\\\"), Some((1, 5, "unterminated character literal".to_owned())));
+    }
+}
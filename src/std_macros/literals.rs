@@ -0,0 +1,282 @@
+use proc_macro2::{Ident, Literal, Span, TokenTree};
+use std::cmp::Ordering;
+use syn::parse::{Parse, ParseStream};
+use syn::Token;
+
+/// The suffixes of integer literals, along with the range of values of their types that fit in an `i128`. Proc
+/// macros can't find the pointer width of the target, so pointer sized integers are assumed to be 64 bits wide.
+const INT_TYPES: &[(&str, i128, i128)] = &[
+    ("i8", i8::MIN as i128, i8::MAX as i128),
+    ("i16", i16::MIN as i128, i16::MAX as i128),
+    ("i32", i32::MIN as i128, i32::MAX as i128),
+    ("i64", i64::MIN as i128, i64::MAX as i128),
+    ("i128", i128::MIN, i128::MAX),
+    ("isize", i64::MIN as i128, i64::MAX as i128),
+    ("u8", 0, u8::MAX as i128),
+    ("u16", 0, u16::MAX as i128),
+    ("u32", 0, u32::MAX as i128),
+    ("u64", 0, u64::MAX as i128),
+    ("u128", 0, i128::MAX),
+    ("usize", 0, u64::MAX as i128),
+];
+
+/// An integer literal, which may be negated, like `-12i32`. The value is kept as a sign and magnitude so that every
+/// `i128` and every `u128` can be represented.
+#[derive(Clone)]
+pub struct IntLit {
+    pub negative: bool,
+    pub magnitude: u128,
+    pub suffix: Option<&'static str>,
+    pub span: Span,
+}
+
+impl Parse for IntLit {
+    fn parse(input: ParseStream) -> syn::Result<Self> {
+        let negated = input.parse::<Option<Token![-]>>()?.is_some();
+        let literal: Literal = input
+            .parse()
+            .map_err(|_| input.error("expected an integer literal"))?;
+        Self::from_literal(negated, &literal)
+    }
+}
+
+impl IntLit {
+    /// Reads an integer literal that has already been parsed, along with whether it was negated.
+    pub fn from_literal(negated: bool, literal: &Literal) -> syn::Result<Self> {
+        let span = literal.span();
+        let int = match litrs::Literal::from(literal) {
+            litrs::Literal::Integer(int) => int,
+            _ => return Err(syn::Error::new(span, "expected an integer literal")),
+        };
+        let suffix = int_suffix(int.suffix(), span)?;
+        let magnitude = int
+            .value::<u128>()
+            .ok_or_else(|| syn::Error::new(span, format!("`{}` is too large", literal)))?;
+
+        Ok(Self {
+            negative: negated && magnitude != 0,
+            magnitude,
+            suffix,
+            span,
+        })
+    }
+
+    /// Creates a literal with the value of an `i128`.
+    pub fn from_i128(value: i128, suffix: Option<&'static str>, span: Span) -> Self {
+        Self {
+            negative: value < 0,
+            magnitude: value.unsigned_abs(),
+            suffix,
+            span,
+        }
+    }
+
+    /// Creates a literal with the value of a `u128`.
+    pub fn from_u128(value: u128, suffix: Option<&'static str>, span: Span) -> Self {
+        Self {
+            negative: false,
+            magnitude: value,
+            suffix,
+            span,
+        }
+    }
+
+    /// The value of the literal, if it fits in an `i128`.
+    pub fn to_i128(&self) -> Option<i128> {
+        match self.negative {
+            true => 0i128.checked_sub_unsigned(self.magnitude),
+            false => i128::try_from(self.magnitude).ok(),
+        }
+    }
+
+    /// The value of the literal, if it isn't negative.
+    pub fn to_u128(&self) -> Option<u128> {
+        (!self.negative).then_some(self.magnitude)
+    }
+
+    /// Whether the value of the literal can have the type `suffix`.
+    pub fn fits(&self, suffix: &str) -> bool {
+        match (suffix, self.to_i128()) {
+            ("u128", _) => !self.negative,
+            (_, Some(value)) => {
+                let (min, max) = int_range(suffix);
+                min <= value && value <= max
+            }
+            (_, None) => false,
+        }
+    }
+
+    /// Orders the values of two literals.
+    pub fn cmp_value(&self, other: &Self) -> Ordering {
+        match (self.negative, other.negative) {
+            (false, false) => self.magnitude.cmp(&other.magnitude),
+            (true, true) => other.magnitude.cmp(&self.magnitude),
+            (false, true) => Ordering::Greater,
+            (true, false) => Ordering::Less,
+        }
+    }
+
+    /// The error for a literal whose value is out of the range that an operation can work with.
+    pub fn out_of_range(&self, suffix: Option<&str>) -> syn::Error {
+        let sign = if self.negative { "-" } else { "" };
+        let message = match suffix {
+            Some(suffix) => format!("`{}{}` is out of range for `{}`", sign, self.magnitude, suffix),
+            None => format!("`{}{}` is too large", sign, self.magnitude),
+        };
+        syn::Error::new(self.span, message)
+    }
+
+    /// Finds the suffix shared by some literals. Unsuffixed literals can be used with literals of any type.
+    pub fn common_suffix(literals: &[IntLit]) -> syn::Result<Option<&'static str>> {
+        let mut common: Option<&IntLit> = None;
+        for literal in literals.iter().filter(|literal| literal.suffix.is_some()) {
+            match common {
+                Some(common) if common.suffix != literal.suffix => {
+                    return Err(syn::Error::new(
+                        literal.span,
+                        format!(
+                            "mismatched integer types, expected `{}` but found `{}`",
+                            common.suffix.unwrap_or_default(),
+                            literal.suffix.unwrap_or_default()
+                        ),
+                    ))
+                }
+                Some(_) => {}
+                None => common = Some(literal),
+            }
+        }
+        Ok(common.and_then(|common| common.suffix))
+    }
+}
+
+/// A float literal, which may be negated. Integer literals with a float suffix, like `1f32`, are float literals too.
+pub struct FloatLit {
+    pub value: f64,
+    pub suffix: String,
+}
+
+impl FloatLit {
+    /// Reads a float literal that has already been parsed, along with whether it was negated.
+    pub fn from_literal(negated: bool, literal: &Literal) -> syn::Result<Self> {
+        let err = || syn::Error::new(literal.span(), "expected a float literal");
+        let (value, suffix) = match litrs::Literal::from(literal) {
+            litrs::Literal::Integer(int) if is_float_suffix(int.suffix()) => {
+                (int.value::<u128>().ok_or_else(err)? as f64, int.suffix().to_owned())
+            }
+            litrs::Literal::Float(float) => {
+                let value: f64 = float.number_part().replace('_', "").parse().map_err(|_| err())?;
+                (value, float.suffix().to_owned())
+            }
+            _ => return Err(err()),
+        };
+
+        Ok(Self {
+            value: if negated { -value } else { value },
+            suffix,
+        })
+    }
+}
+
+/// Whether an integer literal's suffix makes it a float literal.
+pub fn is_float_suffix(suffix: &str) -> bool {
+    matches!(suffix, "f32" | "f64")
+}
+
+/// A string literal, which may be a raw string, decoded with `litrs` so that escapes are handled.
+pub struct StrLit {
+    pub value: String,
+    pub span: Span,
+}
+
+impl Parse for StrLit {
+    fn parse(input: ParseStream) -> syn::Result<Self> {
+        let literal: Literal = input
+            .parse()
+            .map_err(|_| input.error("expected a string literal"))?;
+        Self::from_literal(&literal)
+    }
+}
+
+impl StrLit {
+    /// Reads a string literal that has already been parsed.
+    pub fn from_literal(literal: &Literal) -> syn::Result<Self> {
+        let span = literal.span();
+        match litrs::Literal::from(literal) {
+            litrs::Literal::String(s) => Ok(Self {
+                value: s.value().to_owned(),
+                span,
+            }),
+            _ => Err(syn::Error::new(span, "expected a string literal")),
+        }
+    }
+}
+
+/// Finds the integer type given by a literal's suffix, if it has one.
+pub fn int_suffix(suffix: &str, span: Span) -> syn::Result<Option<&'static str>> {
+    if suffix.is_empty() {
+        return Ok(None);
+    }
+    INT_TYPES
+        .iter()
+        .map(|(name, _, _)| Some(*name))
+        .find(|name| *name == Some(suffix))
+        .ok_or_else(|| syn::Error::new(span, format!("unknown integer suffix `{}`", suffix)))
+}
+
+/// The smallest and largest values of the integer type given by a suffix.
+pub fn int_range(suffix: &str) -> (i128, i128) {
+    let (_, min, max) = INT_TYPES
+        .iter()
+        .find(|(name, _, _)| *name == suffix)
+        .expect("suffixes are checked when parsed");
+    (*min, *max)
+}
+
+/// The number of bits in an integer type, and whether it is signed.
+pub fn int_width(suffix: &str) -> (u32, bool) {
+    match suffix {
+        "i8" => (8, true),
+        "i16" => (16, true),
+        "i32" => (32, true),
+        "i64" | "isize" => (64, true),
+        "i128" => (128, true),
+        "u8" => (8, false),
+        "u16" => (16, false),
+        "u32" => (32, false),
+        "u64" | "usize" => (64, false),
+        _ => (128, false),
+    }
+}
+
+/// Creates an integer literal, checking that the value fits in the type given by the suffix. Errors are reported at
+/// the given span, while the literal itself has a call-site span since it doesn't appear in the input.
+pub fn int_literal(value: i128, suffix: Option<&str>, span: Span) -> syn::Result<TokenTree> {
+    if let Some(suffix) = suffix {
+        let (min, max) = int_range(suffix);
+        if value < min || value > max {
+            return Err(syn::Error::new(
+                span,
+                format!("`{}` is out of range for `{}`", value, suffix),
+            ));
+        }
+    }
+
+    let literal: Literal = format!("{}{}", value, suffix.unwrap_or_default())
+        .parse()
+        .expect("integer literals are valid literals");
+    Ok(TokenTree::Literal(literal))
+}
+
+/// Creates an integer literal from a `u128`, which may be too large for [`int_literal`]. Every `u128` fits in the
+/// types that these values are used for, which are `u128` and unsuffixed literals.
+pub fn u128_literal(value: u128, suffix: Option<&str>) -> TokenTree {
+    let literal: Literal = format!("{}{}", value, suffix.unwrap_or_default())
+        .parse()
+        .expect("integer literals are valid literals");
+    TokenTree::Literal(literal)
+}
+
+/// Creates a `true` or `false` token.
+pub fn bool_token(value: bool) -> TokenTree {
+    TokenTree::Ident(Ident::new(if value { "true" } else { "false" }, Span::call_site()))
+}
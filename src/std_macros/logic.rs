@@ -0,0 +1,114 @@
+use super::count::split_items;
+use super::literals::bool_token;
+use crate::cps_macro::flatten_none_groups;
+use proc_macro2::{TokenStream, TokenTree};
+use syn::ext::IdentExt;
+use syn::parse::Parser;
+use syn::punctuated::Punctuated;
+use syn::spanned::Spanned;
+use syn::{Ident, LitBool, Token};
+
+/// Checks whether two token streams are made of the same tokens, ignoring spans and invisible groups.
+fn tokens_eq(lhs: TokenStream, rhs: TokenStream) -> bool {
+    let lhs: Vec<TokenTree> = flatten_none_groups(lhs).into_iter().collect();
+    let rhs: Vec<TokenTree> = flatten_none_groups(rhs).into_iter().collect();
+    lhs.len() == rhs.len()
+        && lhs.into_iter().zip(rhs).all(|pair| match pair {
+            (TokenTree::Group(lhs), TokenTree::Group(rhs)) => {
+                lhs.delimiter() == rhs.delimiter() && tokens_eq(lhs.stream(), rhs.stream())
+            }
+            (TokenTree::Ident(lhs), TokenTree::Ident(rhs)) => lhs == rhs,
+            (TokenTree::Punct(lhs), TokenTree::Punct(rhs)) => {
+                lhs.as_char() == rhs.as_char() && lhs.spacing() == rhs.spacing()
+            }
+            (TokenTree::Literal(lhs), TokenTree::Literal(rhs)) => lhs.to_string() == rhs.to_string(),
+            _ => false,
+        })
+}
+
+fn parse_token_pair(tokens: TokenStream) -> syn::Result<(TokenStream, TokenStream)> {
+    let span = tokens.span();
+    let mut items = split_items(tokens)?.into_iter();
+    match (items.next(), items.next(), items.next()) {
+        (Some(lhs), Some(rhs), None) => Ok((lhs, rhs)),
+        _ => Err(syn::Error::new(span, "expected two token streams separated by a comma")),
+    }
+}
+
+fn parse_bools(tokens: TokenStream) -> syn::Result<Vec<bool>> {
+    Ok(Punctuated::<LitBool, Token![,]>::parse_terminated
+        .parse2(flatten_none_groups(tokens))?
+        .into_iter()
+        .map(|b| b.value)
+        .collect())
+}
+
+super::impl_std_cps!(
+    fn eq(tokens: proc_macro2::TokenStream) -> syn::Result<proc_macro2::TokenTree> {
+        let (lhs, rhs) = parse_token_pair(tokens)?;
+        Ok(bool_token(tokens_eq(lhs, rhs)))
+    }
+);
+
+super::impl_std_cps!(
+    fn ne(tokens: proc_macro2::TokenStream) -> syn::Result<proc_macro2::TokenTree> {
+        let (lhs, rhs) = parse_token_pair(tokens)?;
+        Ok(bool_token(!tokens_eq(lhs, rhs)))
+    }
+);
+
+super::impl_std_cps!(
+    fn ident_eq(tokens: proc_macro2::TokenStream) -> syn::Result<proc_macro2::TokenTree> {
+        let tokens = flatten_none_groups(tokens);
+        let span = tokens.span();
+        let parser = |input: syn::parse::ParseStream| Punctuated::<Ident, Token![,]>::parse_terminated_with(input, Ident::parse_any);
+        let idents: Vec<Ident> = parser.parse2(tokens)?.into_iter().collect();
+        match idents.as_slice() {
+            [lhs, rhs] => Ok(bool_token(lhs.unraw() == rhs.unraw())),
+            _ => Err(syn::Error::new(span, "expected two identifiers")),
+        }
+    }
+);
+
+super::impl_std_cps!(
+    fn not(tokens: proc_macro2::TokenStream) -> syn::Result<proc_macro2::TokenTree> {
+        let value: LitBool = syn::parse2(flatten_none_groups(tokens))?;
+        Ok(bool_token(!value.value))
+    }
+);
+
+super::impl_std_cps!(
+    fn and(tokens: proc_macro2::TokenStream) -> syn::Result<proc_macro2::TokenTree> {
+        Ok(bool_token(parse_bools(tokens)?.into_iter().all(|b| b)))
+    }
+);
+
+super::impl_std_cps!(
+    fn or(tokens: proc_macro2::TokenStream) -> syn::Result<proc_macro2::TokenTree> {
+        Ok(bool_token(parse_bools(tokens)?.into_iter().any(|b| b)))
+    }
+);
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use quote::quote;
+
+    #[test]
+    fn token_equality() {
+        assert!(tokens_eq(quote! { a::b(c, [d]) }, quote! { a :: b ( c , [ d ] ) }));
+        assert!(tokens_eq(quote! { r#type 1u8 }, quote! { r#type 1u8 }));
+        assert!(!tokens_eq(quote! { a < = b }, quote! { a <= b }));
+        assert!(!tokens_eq(quote! { (a) }, quote! { [a] }));
+        assert!(!tokens_eq(quote! { 1 }, quote! { 0x1 }));
+    }
+
+    #[test]
+    fn pairs() {
+        assert!(parse_token_pair(quote! { a b, (c, d) }).is_ok());
+        assert_eq!(
+            parse_token_pair(quote! { a, b, c }).map_err(|e| e.to_string()).err(),
+            Some("expected two token streams separated by a comma".to_owned())
+        );
+    }
+}
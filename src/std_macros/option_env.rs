@@ -0,0 +1,25 @@
+use super::env::{read_var, EnvInput};
+use crate::cps_macro::flatten_none_groups;
+use proc_macro2::{Literal, TokenStream};
+use quote::quote;
+
+fn do_option_env(tokens: TokenStream) -> syn::Result<TokenStream> {
+    let input: EnvInput = syn::parse2(flatten_none_groups(tokens))?;
+    if let Some(message) = input.message {
+        return Err(syn::Error::new(message.span, "`option_env!` takes only the name of a variable"));
+    }
+
+    Ok(match read_var(&input.name)? {
+        Some(value) => {
+            let value = Literal::string(&value);
+            quote! { Some(#value) }
+        }
+        None => quote! { None },
+    })
+}
+
+super::impl_std_cps!(
+    fn option_env(tokens: proc_macro2::TokenStream) -> syn::Result<proc_macro2::TokenStream> {
+        option_env::do_option_env(tokens)
+    }
+);
@@ -0,0 +1,57 @@
+use proc_macro2::Span;
+use syn::parse::ParseStream;
+use syn::Attribute;
+
+/// Options given to a builtin as attributes before its arguments, like `cps::ident!(#[raw] #[span($x)] ...)`.
+pub struct Options {
+    attrs: Vec<Attribute>,
+}
+
+impl Options {
+    /// Parses any options at the start of the input, checking that each is one of those that the builtin accepts.
+    pub fn parse(input: ParseStream, allowed: &[&str]) -> syn::Result<Self> {
+        let attrs = input.call(Attribute::parse_outer)?;
+        for attr in &attrs {
+            if !allowed.iter().any(|name| attr.path().is_ident(name)) {
+                let expected = allowed
+                    .iter()
+                    .map(|name| format!("`#[{}]`", name))
+                    .collect::<Vec<_>>()
+                    .join(", ");
+                return Err(syn::Error::new_spanned(
+                    attr.path(),
+                    format!("unknown option, expected one of {}", expected),
+                ));
+            }
+        }
+
+        Ok(Self { attrs })
+    }
+
+    fn get(&self, name: &str) -> Option<&Attribute> {
+        self.attrs.iter().find(|attr| attr.path().is_ident(name))
+    }
+
+    /// Whether an option without arguments, like `#[raw]`, was given.
+    pub fn flag(&self, name: &str) -> syn::Result<bool> {
+        match self.get(name) {
+            Some(attr) => attr.meta.require_path_only().map(|_| true),
+            None => Ok(false),
+        }
+    }
+
+    /// The span of the token given with `#[span(...)]`, if there was one.
+    pub fn span(&self) -> syn::Result<Option<Span>> {
+        let attr = match self.get("span") {
+            Some(attr) => attr,
+            None => return Ok(None),
+        };
+
+        let list = attr.meta.require_list()?;
+        let mut tokens = list.tokens.clone().into_iter();
+        match (tokens.next(), tokens.next()) {
+            (Some(tt), None) => Ok(Some(tt.span())),
+            _ => Err(syn::Error::new_spanned(attr, "expected a single token to take the span of: `#[span(token)]`")),
+        }
+    }
+}
@@ -0,0 +1,119 @@
+use super::literals::StrLit;
+use super::options::Options;
+use std::path::{Path, PathBuf};
+use syn::parse::{Parse, ParseStream};
+use syn::Token;
+
+/// The path given to one of the include builtins, like `cps::include_str!(#[manifest_dir] "data/words.txt")`.
+pub struct IncludePath {
+    pub path: StrLit,
+    manifest_dir: bool,
+}
+
+impl Parse for IncludePath {
+    fn parse(input: ParseStream) -> syn::Result<Self> {
+        let options = Options::parse(input, &["manifest_dir"])?;
+        let path = Self::new(input.parse()?, &options)?;
+        input.parse::<Option<Token![,]>>()?;
+
+        Ok(path)
+    }
+}
+
+impl IncludePath {
+    /// Creates a path for a builtin that takes other options as well as `#[manifest_dir]`.
+    pub fn new(path: StrLit, options: &Options) -> syn::Result<Self> {
+        Ok(Self {
+            path,
+            manifest_dir: options.flag("manifest_dir")?,
+        })
+    }
+
+    /// Finds the file that the path refers to. Relative paths are resolved against the directory of the source file
+    /// that the path literal was written in, like std's `include!`, or against the directory of the crate's manifest
+    /// if `#[manifest_dir]` was given or the source file isn't known.
+    pub fn resolve(&self) -> syn::Result<PathBuf> {
+        let path = Path::new(&self.path.value);
+        if path.is_absolute() {
+            return Ok(path.to_owned());
+        }
+
+        let base = match self.manifest_dir {
+            true => None,
+            false => self.source_dir(),
+        };
+        let base = match base {
+            Some(base) => base,
+            None => std::env::var_os("CARGO_MANIFEST_DIR").map(PathBuf::from).ok_or_else(|| {
+                syn::Error::new(
+                    self.path.span,
+                    "`CARGO_MANIFEST_DIR` is not set, so the path can't be resolved against the manifest directory",
+                )
+            })?,
+        };
+        Ok(base.join(path))
+    }
+
+    /// The directory of the source file containing the path literal, if it is a file on disk.
+    fn source_dir(&self) -> Option<PathBuf> {
+        let file = self.path.span.local_file()?;
+        let file = match file.is_absolute() {
+            true => file,
+            // Relative source paths are relative to the directory that the compiler was started in
+            false => std::env::current_dir().ok()?.join(file),
+        };
+        match file.is_file() {
+            true => file.parent().map(Path::to_owned),
+            false => None,
+        }
+    }
+
+    /// Reads the file that the path refers to, reporting the path that was tried if it can't be read.
+    pub fn read(&self) -> syn::Result<(PathBuf, Vec<u8>)> {
+        let path = self.resolve()?;
+        match std::fs::read(&path) {
+            Ok(contents) => Ok((path, contents)),
+            Err(e) => Err(syn::Error::new(
+                self.path.span,
+                format!("couldn't read `{}`: {}", path.display(), e),
+            )),
+        }
+    }
+
+    /// Reads the file that the path refers to as UTF-8 text.
+    pub fn read_to_string(&self) -> syn::Result<(PathBuf, String)> {
+        let (path, contents) = self.read()?;
+        match String::from_utf8(contents) {
+            Ok(contents) => Ok((path, contents)),
+            Err(_) => Err(syn::Error::new(
+                self.path.span,
+                format!("`{}` wasn't a UTF-8 file", path.display()),
+            )),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use quote::quote;
+
+    #[test]
+    fn manifest_dir() {
+        let path: IncludePath = syn::parse2(quote! { #[manifest_dir] "tests/test_file.txt" }).unwrap();
+        let expected = Path::new(env!("CARGO_MANIFEST_DIR")).join("tests/test_file.txt");
+        assert_eq!(path.resolve().ok(), Some(expected));
+    }
+
+    #[test]
+    fn missing_file() {
+        let path: IncludePath = syn::parse2(quote! { #[manifest_dir] "tests/missing.txt" }).unwrap();
+        let expected = Path::new(env!("CARGO_MANIFEST_DIR")).join("tests/missing.txt");
+        let error = path.read().err().map(|e| e.to_string()).unwrap_or_default();
+        assert!(
+            error.starts_with(&format!("couldn't read `{}`: ", expected.display())),
+            "{}",
+            error
+        );
+    }
+}
@@ -0,0 +1,183 @@
+use super::literals::StrLit;
+use super::options::Options;
+use super::paths::IncludePath;
+use crate::cps_macro::flatten_none_groups;
+use proc_macro2::Literal;
+use quote::quote;
+use std::path::{Path, PathBuf};
+use syn::parse::{Parse, ParseStream};
+use syn::Token;
+
+/// The arguments to `cps::read_dir!`: a directory, an optional pattern for file names, and options.
+pub struct ReadDirInput {
+    dir: IncludePath,
+    pattern: Option<StrLit>,
+    recursive: bool,
+    paths: bool,
+}
+
+impl Parse for ReadDirInput {
+    fn parse(input: ParseStream) -> syn::Result<Self> {
+        let options = Options::parse(input, &["manifest_dir", "recursive", "paths"])?;
+        let dir = IncludePath::new(input.parse()?, &options)?;
+        let mut pattern = None;
+        if input.parse::<Option<Token![,]>>()?.is_some() && !input.is_empty() {
+            pattern = Some(input.parse()?);
+            input.parse::<Option<Token![,]>>()?;
+        }
+        if !input.is_empty() {
+            return Err(input.error("unexpected extra argument"));
+        }
+
+        Ok(Self {
+            dir,
+            pattern,
+            recursive: options.flag("recursive")?,
+            paths: options.flag("paths")?,
+        })
+    }
+}
+
+/// A file found in a directory, with its path relative to the directory using `/` separators.
+pub struct ListedFile {
+    pub name: String,
+    pub path: PathBuf,
+}
+
+/// Checks whether a file name matches a pattern, where `*` matches any number of characters and `?` matches one.
+fn matches_pattern(pattern: &[char], name: &[char]) -> bool {
+    match pattern.split_first() {
+        None => name.is_empty(),
+        Some(('*', rest)) => (0..=name.len()).any(|skip| matches_pattern(rest, &name[skip..])),
+        Some(('?', rest)) => !name.is_empty() && matches_pattern(rest, &name[1..]),
+        Some((c, rest)) => name.first() == Some(c) && matches_pattern(rest, &name[1..]),
+    }
+}
+
+impl ReadDirInput {
+    /// Lists the files in the directory that match the pattern, sorted by their relative paths.
+    pub fn files(&self) -> syn::Result<Vec<ListedFile>> {
+        let dir = self.dir.resolve()?;
+        let pattern: Option<Vec<char>> = self.pattern.as_ref().map(|pattern| pattern.value.chars().collect());
+
+        let mut files = Vec::new();
+        self.collect_files(&dir, "", pattern.as_deref(), &mut Vec::new(), &mut files)?;
+        files.sort_by(|a, b| a.name.cmp(&b.name));
+        Ok(files)
+    }
+
+    fn collect_files(
+        &self,
+        dir: &Path,
+        prefix: &str,
+        pattern: Option<&[char]>,
+        ancestors: &mut Vec<PathBuf>,
+        files: &mut Vec<ListedFile>,
+    ) -> syn::Result<()> {
+        let error = |e: std::io::Error| {
+            syn::Error::new(
+                self.dir.path.span,
+                format!("couldn't read directory `{}`: {}", dir.display(), e),
+            )
+        };
+
+        // A symlink can lead back to a directory that is already being listed, which would never finish
+        let canonical = dir.canonicalize().map_err(error)?;
+        if ancestors.contains(&canonical) {
+            return Ok(());
+        }
+        ancestors.push(canonical);
+        for entry in std::fs::read_dir(dir).map_err(error)? {
+            let entry = entry.map_err(error)?;
+            let path = entry.path();
+            let file_name = entry.file_name().into_string().map_err(|_| {
+                syn::Error::new(
+                    self.dir.path.span,
+                    format!("`{}` doesn't have a UTF-8 name", path.display()),
+                )
+            })?;
+            let name = format!("{}{}", prefix, file_name);
+
+            let file_type = entry.file_type().map_err(error)?;
+            if file_type.is_dir() || (file_type.is_symlink() && path.is_dir()) {
+                if self.recursive {
+                    self.collect_files(&path, &format!("{}/", name), pattern, ancestors, files)?;
+                }
+                continue;
+            }
+
+            let chars: Vec<char> = file_name.chars().collect();
+            let matched = match pattern {
+                Some(pattern) => matches_pattern(pattern, &chars),
+                None => true,
+            };
+            if matched {
+                files.push(ListedFile { name, path });
+            }
+        }
+        ancestors.pop();
+        Ok(())
+    }
+}
+
+fn do_read_dir(tokens: proc_macro2::TokenStream) -> syn::Result<proc_macro2::TokenStream> {
+    let input: ReadDirInput = syn::parse2(flatten_none_groups(tokens))?;
+    let mut names = Vec::new();
+    for file in input.files()? {
+        let name = match input.paths {
+            true => file.path.to_str().map(str::to_owned).ok_or_else(|| {
+                syn::Error::new(
+                    input.dir.path.span,
+                    format!("`{}` doesn't have a UTF-8 path", file.path.display()),
+                )
+            })?,
+            false => file.name,
+        };
+        names.push(Literal::string(&name));
+    }
+    Ok(quote! { #(#names)* })
+}
+
+super::impl_std_cps!(
+    fn read_dir(tokens: proc_macro2::TokenStream) -> syn::Result<proc_macro2::TokenStream> {
+        do_read_dir(tokens)
+    }
+);
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn matches(pattern: &str, name: &str) -> bool {
+        let pattern: Vec<char> = pattern.chars().collect();
+        let name: Vec<char> = name.chars().collect();
+        matches_pattern(&pattern, &name)
+    }
+
+    #[test]
+    fn patterns() {
+        assert!(matches("*.sql", "create.sql"));
+        assert!(matches("*.sql", ".sql"));
+        assert!(!matches("*.sql", "create.sql.bak"));
+        assert!(matches("test_?.rs", "test_1.rs"));
+        assert!(!matches("test_?.rs", "test_12.rs"));
+        assert!(matches("*_*", "a_b_c"));
+        assert!(matches("", ""));
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn symlink_cycles() {
+        let dir = std::env::temp_dir().join(format!("cps_read_dir_cycle_{}", std::process::id()));
+        let _ = std::fs::remove_dir_all(&dir);
+        std::fs::create_dir_all(dir.join("inner")).unwrap();
+        std::fs::write(dir.join("inner/file.txt"), "").unwrap();
+        std::os::unix::fs::symlink(&dir, dir.join("inner/parent")).unwrap();
+
+        let path = dir.to_str().unwrap();
+        let input: ReadDirInput = syn::parse2(quote! { #[recursive] #path }).unwrap();
+        let names: Vec<String> = input.files().unwrap().into_iter().map(|file| file.name).collect();
+        std::fs::remove_dir_all(&dir).unwrap();
+        assert_eq!(names, vec!["inner/file.txt".to_owned()]);
+    }
+}
@@ -1,5 +1,5 @@
 super::impl_std_cps!(
-    fn stringify(tokens: proc_macro2::TokenStream) -> proc_macro2::Literal {
-        proc_macro2::Literal::string(&tokens.to_string())
+    fn stringify(tokens: proc_macro2::TokenStream) -> syn::Result<proc_macro2::Literal> {
+        Ok(proc_macro2::Literal::string(&tokens.to_string()))
     }
 );
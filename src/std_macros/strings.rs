@@ -0,0 +1,210 @@
+use super::literals::{bool_token, IntLit, StrLit};
+use crate::cps_macro::flatten_none_groups;
+use proc_macro2::{Literal, TokenStream};
+use quote::quote;
+use syn::parse::{ParseStream, Parser};
+use syn::Token;
+
+/// Parses a builtin's comma-separated arguments, allowing a trailing comma.
+fn parse_args<T>(tokens: TokenStream, parser: impl FnOnce(ParseStream) -> syn::Result<T>) -> syn::Result<T> {
+    (|input: ParseStream| {
+        let args = parser(input)?;
+        input.parse::<Option<Token![,]>>()?;
+        if !input.is_empty() {
+            return Err(input.error("unexpected extra argument"));
+        }
+        Ok(args)
+    })
+    .parse2(flatten_none_groups(tokens))
+}
+
+fn parse_string(tokens: TokenStream) -> syn::Result<String> {
+    parse_args(tokens, |input| Ok(input.parse::<StrLit>()?.value))
+}
+
+fn parse_string_pair(tokens: TokenStream) -> syn::Result<(String, String)> {
+    parse_args(tokens, |input| {
+        let lhs: StrLit = input.parse()?;
+        input.parse::<Token![,]>()?;
+        let rhs: StrLit = input.parse()?;
+        Ok((lhs.value, rhs.value))
+    })
+}
+
+/// Parses an integer argument that is used as a count or index.
+fn parse_usize(input: ParseStream) -> syn::Result<usize> {
+    let int: IntLit = input.parse()?;
+    int.to_u128()
+        .and_then(|value| usize::try_from(value).ok())
+        .ok_or_else(|| syn::Error::new(int.span, "expected a non-negative integer"))
+}
+
+fn replace(tokens: TokenStream) -> syn::Result<String> {
+    let (value, from, to) = parse_args(tokens, |input| {
+        let value: StrLit = input.parse()?;
+        input.parse::<Token![,]>()?;
+        let from: StrLit = input.parse()?;
+        input.parse::<Token![,]>()?;
+        let to: StrLit = input.parse()?;
+        if from.value.is_empty() {
+            return Err(syn::Error::new(from.span, "cannot replace an empty string"));
+        }
+        Ok((value.value, from.value, to.value))
+    })?;
+    Ok(value.replace(&from, &to))
+}
+
+fn split(tokens: TokenStream) -> syn::Result<Vec<String>> {
+    let (value, separator) = parse_args(tokens, |input| {
+        let value: StrLit = input.parse()?;
+        input.parse::<Token![,]>()?;
+        let separator: StrLit = input.parse()?;
+        if separator.value.is_empty() {
+            return Err(syn::Error::new(separator.span, "cannot split on an empty string"));
+        }
+        Ok((value.value, separator.value))
+    })?;
+    Ok(value.split(&separator).map(str::to_owned).collect())
+}
+
+fn substr(tokens: TokenStream) -> syn::Result<String> {
+    let (value, start, end) = parse_args(tokens, |input| {
+        let value: StrLit = input.parse()?;
+        input.parse::<Token![,]>()?;
+        let start = parse_usize(input)?;
+        let end = match input.parse::<Option<Token![,]>>()? {
+            Some(_) if !input.is_empty() => Some(parse_usize(input)?),
+            _ => None,
+        };
+
+        let len = value.value.chars().count();
+        let end_index = end.unwrap_or(len);
+        if start > end_index || end_index > len {
+            return Err(syn::Error::new(
+                value.span,
+                format!("char range {}..{} is out of bounds for a string of length {}", start, end_index, len),
+            ));
+        }
+        Ok((value.value, start, end_index))
+    })?;
+    Ok(value.chars().skip(start).take(end - start).collect())
+}
+
+/// The longest string that `str_repeat!` will create, which is far longer than any literal should be.
+const MAX_REPEATED_LEN: usize = 1 << 24;
+
+fn repeat(tokens: TokenStream) -> syn::Result<String> {
+    let (value, count) = parse_args(tokens, |input| {
+        let value: StrLit = input.parse()?;
+        input.parse::<Token![,]>()?;
+        let span = input.span();
+        let count = parse_usize(input)?;
+        match value.value.len().checked_mul(count) {
+            Some(len) if len <= MAX_REPEATED_LEN => Ok((value.value, count)),
+            _ => Err(syn::Error::new(
+                span,
+                format!("repeated string would be longer than {} bytes", MAX_REPEATED_LEN),
+            )),
+        }
+    })?;
+    Ok(value.repeat(count))
+}
+
+super::impl_std_cps!(
+    fn str_replace(tokens: proc_macro2::TokenStream) -> syn::Result<proc_macro2::Literal> {
+        Ok(Literal::string(&replace(tokens)?))
+    }
+);
+
+super::impl_std_cps!(
+    fn str_split(tokens: proc_macro2::TokenStream) -> syn::Result<proc_macro2::TokenStream> {
+        let parts = split(tokens)?.into_iter().map(|part| Literal::string(&part));
+        Ok(quote! { #(#parts),* })
+    }
+);
+
+super::impl_std_cps!(
+    fn str_trim(tokens: proc_macro2::TokenStream) -> syn::Result<proc_macro2::Literal> {
+        Ok(Literal::string(parse_string(tokens)?.trim()))
+    }
+);
+
+super::impl_std_cps!(
+    fn str_to_upper(tokens: proc_macro2::TokenStream) -> syn::Result<proc_macro2::Literal> {
+        Ok(Literal::string(&parse_string(tokens)?.to_uppercase()))
+    }
+);
+
+super::impl_std_cps!(
+    fn str_to_lower(tokens: proc_macro2::TokenStream) -> syn::Result<proc_macro2::Literal> {
+        Ok(Literal::string(&parse_string(tokens)?.to_lowercase()))
+    }
+);
+
+super::impl_std_cps!(
+    fn str_starts_with(tokens: proc_macro2::TokenStream) -> syn::Result<proc_macro2::TokenTree> {
+        let (value, prefix) = parse_string_pair(tokens)?;
+        Ok(bool_token(value.starts_with(&prefix)))
+    }
+);
+
+super::impl_std_cps!(
+    fn str_ends_with(tokens: proc_macro2::TokenStream) -> syn::Result<proc_macro2::TokenTree> {
+        let (value, suffix) = parse_string_pair(tokens)?;
+        Ok(bool_token(value.ends_with(&suffix)))
+    }
+);
+
+super::impl_std_cps!(
+    fn str_contains(tokens: proc_macro2::TokenStream) -> syn::Result<proc_macro2::TokenTree> {
+        let (value, pattern) = parse_string_pair(tokens)?;
+        Ok(bool_token(value.contains(&pattern)))
+    }
+);
+
+super::impl_std_cps!(
+    fn str_substr(tokens: proc_macro2::TokenStream) -> syn::Result<proc_macro2::Literal> {
+        Ok(Literal::string(&substr(tokens)?))
+    }
+);
+
+super::impl_std_cps!(
+    fn str_repeat(tokens: proc_macro2::TokenStream) -> syn::Result<proc_macro2::Literal> {
+        Ok(Literal::string(&repeat(tokens)?))
+    }
+);
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn result<T>(res: syn::Result<T>) -> Result<T, String> {
+        res.map_err(|e| e.to_string())
+    }
+
+    #[test]
+    fn operations() {
+        assert_eq!(result(replace(quote! { "a-b-c", "-", "::" })), Ok("a::b::c".to_owned()));
+        assert_eq!(result(split(quote! { r"a\b\c", r"\" })), Ok(vec!["a".to_owned(), "b".to_owned(), "c".to_owned()]));
+        assert_eq!(result(substr(quote! { "héllo", 1, 3 })), Ok("él".to_owned()));
+        assert_eq!(result(substr(quote! { "héllo", 2 })), Ok("llo".to_owned()));
+        assert_eq!(result(repeat(quote! { "ab", 3, })), Ok("ababab".to_owned()));
+        assert_eq!(result(parse_string(quote! { "\t\u{41}\n" })), Ok("\tA\n".to_owned()));
+    }
+
+    #[test]
+    fn errors() {
+        assert_eq!(
+            result(substr(quote! { "abc", 2, 4 })),
+            Err("char range 2..4 is out of bounds for a string of length 3".to_owned())
+        );
+        assert_eq!(result(repeat(quote! { "a", -1 })), Err("expected a non-negative integer".to_owned()));
+        assert_eq!(
+            result(repeat(quote! { "ab", 18446744073709551615 })),
+            Err("repeated string would be longer than 16777216 bytes".to_owned())
+        );
+        assert_eq!(result(split(quote! { "a", "" })), Err("cannot split on an empty string".to_owned()));
+        assert_eq!(result(parse_string(quote! { "a", "b" })), Err("unexpected extra argument".to_owned()));
+        assert_eq!(result(parse_string(quote! { 'a' })), Err("expected a string literal".to_owned()));
+    }
+}
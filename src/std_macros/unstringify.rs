@@ -0,0 +1,87 @@
+use super::literals::StrLit;
+use super::options::Options;
+use crate::cps_macro::flatten_none_groups;
+use proc_macro2::{Group, Span, TokenStream, TokenTree};
+use syn::parse::{Parse, ParseStream};
+
+struct UnstringifyInput {
+    span: Option<Span>,
+    string: StrLit,
+}
+
+impl Parse for UnstringifyInput {
+    fn parse(input: ParseStream) -> syn::Result<Self> {
+        let options = Options::parse(input, &["span"])?;
+        let string: StrLit = input.parse()?;
+        if !input.is_empty() {
+            return Err(input.error("expected a single string literal"));
+        }
+
+        Ok(Self {
+            span: options.span()?,
+            string,
+        })
+    }
+}
+
+/// Gives every token, including those nested in groups, the same span.
+pub fn respan(tokens: TokenStream, span: Span) -> TokenStream {
+    tokens
+        .into_iter()
+        .map(|tt| match tt {
+            TokenTree::Group(g) => {
+                let mut new_group = Group::new(g.delimiter(), respan(g.stream(), span));
+                new_group.set_span(span);
+                TokenTree::Group(new_group)
+            }
+            mut tt => {
+                tt.set_span(span);
+                tt
+            }
+        })
+        .collect()
+}
+
+fn unstringify_tokens(tokens: TokenStream) -> syn::Result<TokenStream> {
+    let input: UnstringifyInput = syn::parse2(flatten_none_groups(tokens))?;
+    let parsed: TokenStream = input.string.value.parse().map_err(|e| {
+        syn::Error::new(
+            input.string.span,
+            format!("could not parse {:?} as tokens: {}", input.string.value, e),
+        )
+    })?;
+    Ok(respan(parsed, input.span.unwrap_or(input.string.span)))
+}
+
+super::impl_std_cps!(
+    fn unstringify(tokens: proc_macro2::TokenStream) -> syn::Result<proc_macro2::TokenStream> {
+        unstringify_tokens(tokens)
+    }
+);
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use quote::quote;
+
+    fn result(tokens: TokenStream) -> Result<String, String> {
+        unstringify_tokens(tokens)
+            .map(|tokens| tokens.to_string())
+            .map_err(|e| e.to_string())
+    }
+
+    #[test]
+    fn strings() {
+        assert_eq!(result(quote! { "a + (b, c)" }), Ok("a + (b , c)".to_owned()));
+        assert_eq!(result(quote! { r#"x("y")"# }), Ok("x (\"y\")".to_owned()));
+        assert_eq!(result(quote! { #[span(foo)] "\"\\n\"" }), Ok("\"\\n\"".to_owned()));
+        assert_eq!(result(quote! { "" }), Ok("".to_owned()));
+    }
+
+    #[test]
+    fn errors() {
+        assert!(result(quote! { "(a" }).unwrap_err().starts_with("could not parse \"(a\" as tokens"));
+        assert_eq!(result(quote! { b"a" }), Err("expected a string literal".to_owned()));
+        assert_eq!(result(quote! { "a" "b" }), Err("expected a single string literal".to_owned()));
+    }
+}
@@ -0,0 +1,313 @@
+use crate::parse_macro_decl::{CPSMacroRule, LetBinding, MacroMatch, MacroMatcher};
+use proc_macro2::{Ident, Span, TokenStream, TokenTree};
+use quote::{quote_spanned, ToTokens};
+
+/// The fragment specifiers accepted by `macro_rules!`.
+const FRAGMENT_SPECIFIERS: &[&str] = &[
+    "block",
+    "expr",
+    "expr_2021",
+    "ident",
+    "item",
+    "lifetime",
+    "literal",
+    "meta",
+    "pat",
+    "pat_param",
+    "path",
+    "stmt",
+    "tt",
+    "ty",
+    "vis",
+];
+
+/// Errors and warnings found while checking a macro definition, before any rules are generated.
+#[derive(Default)]
+pub struct Diagnostics {
+    errors: Option<syn::Error>,
+    warnings: Vec<(Span, String)>,
+}
+
+impl Diagnostics {
+    pub fn error(&mut self, span: Span, message: impl std::fmt::Display) {
+        let error = syn::Error::new(span, message);
+        match &mut self.errors {
+            Some(errors) => errors.combine(error),
+            None => self.errors = Some(error),
+        }
+    }
+
+    pub fn warning(&mut self, span: Span, message: impl Into<String>) {
+        self.warnings.push((span, message.into()))
+    }
+
+    /// Discards any warnings, for use where the items that warnings are emitted as aren't allowed.
+    pub fn into_errors(self) -> Option<syn::Error> {
+        self.errors
+    }
+}
+
+impl ToTokens for Diagnostics {
+    fn to_tokens(&self, tokens: &mut TokenStream) {
+        if let Some(errors) = &self.errors {
+            tokens.extend(errors.to_compile_error());
+        }
+
+        // Proc macros can't emit warnings on stable, but using a deprecated item will cause the compiler to emit
+        // one for us, with our message and span
+        for (span, message) in &self.warnings {
+            tokens.extend(quote_spanned! {*span=>
+                const _: () = {
+                    #[deprecated(note = #message)]
+                    #[allow(non_upper_case_globals)]
+                    const cps_warning: () = ();
+                    cps_warning
+                };
+            });
+        }
+    }
+}
+
+/// Collects the metavariables bound by a matcher, along with their fragment specifiers.
+pub fn collect_bindings<'a>(matcher: &'a MacroMatcher, bindings: &mut Vec<(&'a Ident, &'a Ident)>) {
+    for m in &matcher.matches {
+        collect_match_bindings(m, bindings);
+    }
+}
+
+pub fn collect_match_bindings<'a>(m: &'a MacroMatch, bindings: &mut Vec<(&'a Ident, &'a Ident)>) {
+    match m {
+        MacroMatch::Identifier(i) => bindings.push((&i.identifier, &i.macro_frag_spec)),
+        MacroMatch::Repetition(r) => collect_bindings(&r.sub_matches, bindings),
+        MacroMatch::Group(_, g) => collect_bindings(g, bindings),
+        MacroMatch::Ident(_) | MacroMatch::Punct(_) | MacroMatch::Literal(_) => {}
+    }
+}
+
+/// Collects every `$name` referenced in a token stream, skipping `$$` escapes and `$crate`.
+pub fn collect_references(tokens: TokenStream, references: &mut Vec<Ident>) {
+    let mut tokens = tokens.into_iter().peekable();
+    while let Some(tt) = tokens.next() {
+        match tt {
+            TokenTree::Punct(p) if p.as_char() == '$' => match tokens.peek() {
+                Some(TokenTree::Punct(next)) if next.as_char() == '$' => {
+                    tokens.next();
+                }
+                Some(TokenTree::Ident(i)) if i != "crate" => {
+                    references.push(i.clone());
+                    tokens.next();
+                }
+                _ => {}
+            },
+            TokenTree::Group(g) => collect_references(g.stream(), references),
+            _ => {}
+        }
+    }
+}
+
+fn check_fragment_specifiers(bindings: &[(&Ident, &Ident)], diagnostics: &mut Diagnostics) {
+    for (name, frag_spec) in bindings {
+        if !FRAGMENT_SPECIFIERS.iter().any(|spec| *frag_spec == spec) {
+            diagnostics.error(
+                frag_spec.span(),
+                format!(
+                    "invalid fragment specifier `{}` for `${}`, expected one of {}",
+                    frag_spec,
+                    name,
+                    FRAGMENT_SPECIFIERS.join(", ")
+                ),
+            );
+        }
+    }
+}
+
+/// Collects the metavariables referenced by a let binding's macro name and arguments.
+fn collect_binding_references(binding: &LetBinding, references: &mut Vec<Ident>) {
+    if binding.macro_name_indirection.is_some() {
+        match binding.macro_invocation.path.segments.first() {
+            Some(first) if first.ident != "crate" => references.push(first.ident.clone()),
+            _ => {}
+        }
+    }
+    collect_references(binding.macro_invocation.tokens.clone(), references);
+}
+
+/// Checks the metavariables bound and referenced by a rule's pattern and let bindings.
+pub fn validate_rule(rule: &CPSMacroRule, diagnostics: &mut Diagnostics) {
+    let mut arm_bindings = Vec::new();
+    collect_bindings(&rule.pattern, &mut arm_bindings);
+    check_fragment_specifiers(&arm_bindings, diagnostics);
+
+    let let_bindings: Vec<_> = rule
+        .let_bindings
+        .iter()
+        .map(|binding| {
+            let mut bindings = Vec::new();
+            collect_match_bindings(&binding.pattern, &mut bindings);
+            bindings
+        })
+        .collect();
+
+    let mut bound: Vec<&Ident> = arm_bindings.iter().map(|(name, _)| *name).collect();
+    for (i, (binding, bindings)) in rule.let_bindings.iter().zip(&let_bindings).enumerate() {
+        // Everything a let binding refers to must have been bound by the arm or an earlier let binding
+        let mut references = Vec::new();
+        collect_binding_references(binding, &mut references);
+        for reference in references {
+            if bound.iter().any(|name| **name == reference) {
+                continue;
+            }
+            let bound_later = let_bindings[i..]
+                .iter()
+                .flatten()
+                .any(|(name, _)| **name == reference);
+            let message = if bound_later {
+                format!("`${}` is used before the let binding that binds it", reference)
+            } else {
+                format!("`${}` is not bound by the rule pattern or an earlier let binding", reference)
+            };
+            diagnostics.error(reference.span(), message);
+        }
+
+        check_fragment_specifiers(bindings, diagnostics);
+        let previously_bound = bound.len();
+        for (name, _) in bindings {
+            if arm_bindings.iter().any(|(arm_name, _)| arm_name == name) {
+                diagnostics.error(
+                    name.span(),
+                    format!("let binding `${}` shadows a metavariable of the same name in the rule pattern", name),
+                );
+            } else if bound[..previously_bound].contains(name) {
+                diagnostics.error(
+                    name.span(),
+                    format!("`${}` is bound by more than one let binding", name),
+                );
+            }
+            bound.push(name);
+        }
+    }
+
+    // Let bindings should be used by a later let binding or the body
+    for (i, bindings) in let_bindings.iter().enumerate() {
+        let mut references = Vec::new();
+        collect_references(rule.impl_tokens.clone(), &mut references);
+        for later in &rule.let_bindings[i + 1..] {
+            collect_binding_references(later, &mut references);
+        }
+
+        for (name, _) in bindings {
+            if !name.to_string().starts_with('_') && !references.iter().any(|r| r == *name) {
+                diagnostics.warning(
+                    name.span(),
+                    format!(
+                        "let binding `${}` is never used, consider prefixing it with an underscore: `$_{}`",
+                        name, name
+                    ),
+                );
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use quote::quote;
+
+    fn validate(rule: TokenStream) -> (Vec<String>, Vec<String>) {
+        let rule: CPSMacroRule = syn::parse2(rule).expect("test rule did not parse");
+        let mut diagnostics = Diagnostics::default();
+        validate_rule(&rule, &mut diagnostics);
+
+        let errors = diagnostics
+            .errors
+            .map(|errors| errors.into_iter().map(|e| e.to_string()).collect())
+            .unwrap_or_default();
+        let warnings = diagnostics.warnings.into_iter().map(|(_, w)| w).collect();
+        (errors, warnings)
+    }
+
+    #[test]
+    fn valid_rule() {
+        let (errors, warnings) = validate(quote! {
+            ($a:ident $($b:tt)*) =>
+            let $x:tt = foo!($a) in
+            let $cont:ident = bar!($x $($b)*) in
+            let $($y:tt)* = $cont!() in
+            let $_unused:tt = $crate::baz!() in
+            {
+                $($y)*
+            }
+        });
+        assert!(errors.is_empty(), "{:?}", errors);
+        assert!(warnings.is_empty(), "{:?}", warnings);
+    }
+
+    #[test]
+    fn duplicate_let_binding() {
+        let (errors, _) = validate(quote! {
+            () =>
+            let $x:tt = foo!() in
+            let $x:tt = foo!() in
+            { $x }
+        });
+        assert_eq!(errors, vec!["`$x` is bound by more than one let binding"]);
+    }
+
+    #[test]
+    fn shadowed_arm_metavariable() {
+        let (errors, _) = validate(quote! {
+            ($x:tt) =>
+            let $x:tt = foo!() in
+            { $x }
+        });
+        assert_eq!(
+            errors,
+            vec!["let binding `$x` shadows a metavariable of the same name in the rule pattern"]
+        );
+    }
+
+    #[test]
+    fn unused_let_binding() {
+        let (errors, warnings) = validate(quote! {
+            () =>
+            let $x:tt = foo!() in
+            let $y:tt = foo!($x) in
+            { }
+        });
+        assert!(errors.is_empty(), "{:?}", errors);
+        assert_eq!(
+            warnings,
+            vec!["let binding `$y` is never used, consider prefixing it with an underscore: `$_y`"]
+        );
+    }
+
+    #[test]
+    fn unknown_fragment_specifier() {
+        let (errors, _) = validate(quote! {
+            () =>
+            let $x:expression = foo!() in
+            { $x }
+        });
+        assert_eq!(errors.len(), 1);
+        assert!(errors[0].starts_with("invalid fragment specifier `expression` for `$x`"));
+    }
+
+    #[test]
+    fn reference_before_binding() {
+        let (errors, _) = validate(quote! {
+            () =>
+            let $x:tt = foo!($y) in
+            let $y:tt = foo!() in
+            let $z:tt = $w!() in
+            { $x $y $z }
+        });
+        assert_eq!(
+            errors,
+            vec![
+                "`$y` is used before the let binding that binds it",
+                "`$w` is not bound by the rule pattern or an earlier let binding",
+            ]
+        );
+    }
+}
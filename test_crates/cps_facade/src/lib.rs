@@ -0,0 +1,35 @@
+//! A crate that re-exports `cps` under its own name, as facade crates do.
+
+pub use cps::{__call_path, concat, cps, eager, eval, format, include, include_str, stringify};
+
+#[cps(crate = "$crate")]
+#[macro_export]
+macro_rules! facade_macro {
+    () => { BaseCase };
+
+    (stringify) =>
+    let $x:tt = $crate::facade_macro!() in
+    let $y:tt = cps::stringify!($x) in
+    {
+        $y
+    };
+}
+
+pub mod helpers {
+    #[crate::cps(crate = "$crate")]
+    #[macro_export]
+    macro_rules! facade_helper {
+        () => { HelperCase };
+    }
+}
+
+#[cps(crate = "$crate")]
+#[macro_export]
+macro_rules! facade_calls_helper {
+    () =>
+    let $x:tt = $crate::facade_helper!() in
+    let $y:tt = cps::stringify!($x) in
+    {
+        $y
+    };
+}
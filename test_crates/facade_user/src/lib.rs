@@ -0,0 +1 @@
+//! Uses `cps` only through a facade crate, without depending on `cps` directly.
@@ -0,0 +1,84 @@
+use cps_facade::{cps, facade_calls_helper, facade_macro};
+
+#[cps(crate = "cps_facade")]
+macro_rules! macro1 {
+    () => { BaseCase };
+
+    (stringify) =>
+    let $x:tt = macro1!() in
+    let $y:tt = cps::stringify!($x) in
+    {
+        $y
+    };
+
+    (concat) =>
+    let $x:tt = macro1!() in
+    let $x2:tt = cps::stringify!($x) in
+    let $y:tt = cps::concat!("Got: ", $x2) in
+    {
+        $y
+    };
+
+    (format) =>
+    let $x:tt = macro1!() in
+    let $y:tt = cps::format!("Got: {}", $x) in
+    {
+        $y
+    };
+
+    (eager) => {
+        stringify!(cps_facade::eager!(macro1!()))
+    };
+
+    (call $callee:path) =>
+    let $x:tt = $callee!() in
+    let $y:tt = cps::stringify!($x) in
+    {
+        $y
+    };
+}
+
+#[test]
+fn builtin_through_facade() {
+    assert_eq!(macro1!(stringify), "BaseCase");
+}
+
+#[test]
+fn builtin_with_identifier_argument_through_facade() {
+    assert_eq!(macro1!(concat), "Got: BaseCase");
+}
+
+#[test]
+fn format_through_facade() {
+    assert_eq!(macro1!(format), "Got: BaseCase");
+}
+
+#[test]
+fn eager_through_facade() {
+    assert_eq!(macro1!(eager), "BaseCase");
+}
+
+#[test]
+fn exported_facade_macro() {
+    assert_eq!(facade_macro!(stringify), "BaseCase");
+}
+
+#[test]
+fn exported_facade_macro_calls_crate_path() {
+    assert_eq!(facade_calls_helper!(), "HelperCase");
+}
+
+#[test]
+fn path_binding_through_facade() {
+    assert_eq!(macro1!(call cps_facade::facade_helper), "HelperCase");
+}
+
+#[test]
+fn eval_through_facade() {
+    let s = cps_facade::eval! {
+        #![cps(crate = "cps_facade")]
+        let $x:tt = cps::stringify!(a + b) in
+        { $x }
+    };
+    assert_eq!(s, "a + b");
+}
@@ -0,0 +1,36 @@
+//! Checks that CPS macros can be defined and expanded in a `#![no_std]` crate.
+
+#![no_std]
+
+use cps::cps;
+
+#[cps]
+macro_rules! macro1 {
+    (a) => { CaseA };
+    (b) => { CaseB };
+
+    (a_b) =>
+    let $x:tt = macro1!(a) in
+    let $x2:tt = cps::stringify!($x) in
+    let $y:tt = macro1!(b) in
+    let $y2:tt = cps::stringify!($y) in
+    {
+        concat!($x2, $y2)
+    };
+}
+
+#[cps]
+#[macro_export]
+macro_rules! exported_macro1 {
+    () => { BaseCase };
+
+    (stringify) =>
+    let $x:tt = exported_macro1!() in
+    let $y:tt = cps::stringify!($x) in
+    {
+        $y
+    };
+}
+
+pub const A_B: &str = macro1!(a_b);
+pub const BASE_CASE: &str = exported_macro1!(stringify);
@@ -0,0 +1,9 @@
+#[test]
+fn let_bindings_evaluated() {
+    assert_eq!(no_std_test::A_B, "CaseACaseB");
+}
+
+#[test]
+fn exported_macro_evaluated() {
+    assert_eq!(no_std_test::BASE_CASE, "BaseCase");
+}
@@ -0,0 +1,79 @@
+use cps::cps;
+
+#[cps]
+macro_rules! calculate {
+    ($a:literal, $b:literal) =>
+    let $sum:literal = cps::add!($a, $b, 1) in
+    let $difference:literal = cps::sub!($b, $sum) in
+    let $product:literal = cps::mul!($difference, $a) in
+    let $quotient:literal = cps::div!($product, 4) in
+    let $remainder:literal = cps::rem!($product, 4) in
+    let $min:literal = cps::min!($a, $b, $difference) in
+    let $max:literal = cps::max!($a, $b, $difference) in
+    {
+        [$sum, $difference, $product, $quotient, $remainder, $min, $max]
+    };
+}
+
+#[cps]
+macro_rules! typed {
+    ($a:literal) =>
+    let $x:literal = cps::add!($a, 255) in
+    {
+        $x
+    };
+}
+
+#[cps]
+macro_rules! largest {
+    ($a:literal) =>
+    let $x:literal = cps::add!($a, 1) in
+    let $y:literal = cps::max!($a, $x, 1) in
+    {
+        $y
+    };
+}
+
+#[cps]
+macro_rules! branch {
+    (@choose true) => { "Less" };
+    (@choose false) => { "NotLess" };
+
+    ($a:literal, $b:literal) =>
+    let $less:ident = cps::lt!($a, $b) in
+    let $result:literal = branch!(@choose $less) in
+    {
+        $result
+    };
+}
+
+#[cps]
+macro_rules! comparisons {
+    ($a:literal, $b:literal) =>
+    let $lt:tt = cps::lt!($a, $b) in
+    let $le:tt = cps::le!($a, $b) in
+    let $gt:tt = cps::gt!($a, $b) in
+    let $ge:tt = cps::ge!($a, $b) in
+    {
+        [$lt, $le, $gt, $ge]
+    };
+}
+
+#[test]
+fn arithmetic() {
+    assert_eq!(calculate!(3, 5), [9, -4, -12, -3, 0, -4, 5]);
+}
+
+#[test]
+fn suffixes() {
+    let x = typed!(0u8);
+    assert_eq!(x, u8::MAX);
+    assert_eq!(largest!(340282366920938463463374607431768211454u128), u128::MAX);
+}
+
+#[test]
+fn comparison() {
+    assert_eq!(branch!(1, 2), "Less");
+    assert_eq!(branch!(2u32, 2), "NotLess");
+    assert_eq!(comparisons!(-1, -1), [false, true, false, true]);
+}
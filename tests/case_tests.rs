@@ -0,0 +1,57 @@
+use cps::cps;
+
+#[cps]
+macro_rules! accessors {
+    ($field:ident) =>
+    let $getter:ident = cps::to_snake_case!($field) in
+    let $constant:ident = cps::to_upper_snake_case!($field) in
+    let $variant:ident = cps::to_pascal_case!($field) in
+    let $camel:ident = cps::to_camel_case!($field) in
+    {
+        fn $getter() -> &'static str {
+            stringify!($getter $constant $variant $camel)
+        }
+    };
+}
+
+#[cps]
+macro_rules! strings {
+    ($text:literal) =>
+    let $snake:literal = cps::to_snake_case!($text) in
+    let $upper:literal = cps::to_upper_snake_case!($text) in
+    let $camel:literal = cps::to_camel_case!($text) in
+    let $pascal:literal = cps::to_pascal_case!($text) in
+    let $kebab:literal = cps::to_kebab_case!($text) in
+    {
+        [$snake, $upper, $camel, $pascal, $kebab]
+    };
+}
+
+#[cps]
+macro_rules! keyword {
+    ($x:ident) =>
+    let $y:ident = cps::to_snake_case!($x) in
+    {
+        stringify!($y)
+    };
+}
+
+accessors!(FieldName);
+
+#[test]
+fn ident_conversions() {
+    assert_eq!(field_name(), "field_name FIELD_NAME FieldName fieldName");
+}
+
+#[test]
+fn string_conversions() {
+    assert_eq!(
+        strings!("parseHTTPRequest"),
+        ["parse_http_request", "PARSE_HTTP_REQUEST", "parseHttpRequest", "ParseHttpRequest", "parse-http-request"]
+    );
+}
+
+#[test]
+fn keywords_become_raw() {
+    assert_eq!(keyword!(Type), "r#type");
+}
@@ -0,0 +1,61 @@
+use cps::cps;
+
+#[cps]
+macro_rules! shape {
+    ($($x:tt)*) =>
+    let $ident:tt = cps::is_ident!($($x)*) in
+    let $keyword:tt = cps::is_keyword!($($x)*) in
+    let $literal:tt = cps::is_literal!($($x)*) in
+    let $lifetime:tt = cps::is_lifetime!($($x)*) in
+    let $group:tt = cps::is_group!($($x)*) in
+    let $empty:tt = cps::is_empty!($($x)*) in
+    {
+        [$ident, $keyword, $literal, $lifetime, $group, $empty]
+    };
+}
+
+#[cps]
+macro_rules! parses_as {
+    ($($x:tt)*) =>
+    let $ty:tt = cps::is_type!($($x)*) in
+    let $expr:tt = cps::is_expr!($($x)*) in
+    let $path:tt = cps::is_path!($($x)*) in
+    let $item:tt = cps::is_item!($($x)*) in
+    {
+        [$ty, $expr, $path, $item]
+    };
+}
+
+#[cps]
+macro_rules! kinds {
+    ($lit:literal, $group:tt) =>
+    let $lit_kind:ident = cps::literal_kind!($lit) in
+    let $delimiter:ident = cps::group_delimiter!($group) in
+    {
+        (stringify!($lit_kind), stringify!($delimiter))
+    };
+}
+
+#[test]
+fn shapes() {
+    assert_eq!(shape!(foo), [true, false, false, false, false, false]);
+    assert_eq!(shape!(fn), [false, true, false, false, false, false]);
+    assert_eq!(shape!(-1), [false, false, true, false, false, false]);
+    assert_eq!(shape!('a), [false, false, false, true, false, false]);
+    assert_eq!(shape!([a b]), [false, false, false, false, true, false]);
+    assert_eq!(shape!(), [false, false, false, false, false, true]);
+}
+
+#[test]
+fn parsing() {
+    assert_eq!(parses_as!(Vec<u8>), [true, false, true, false]);
+    assert_eq!(parses_as!(foo), [true, true, true, false]);
+    assert_eq!(parses_as!(1 + 2), [false, true, false, false]);
+    assert_eq!(parses_as!(struct Foo;), [false, false, false, true]);
+}
+
+#[test]
+fn kind_names() {
+    assert_eq!(kinds!(b"bytes", {}), ("byte_str", "brace"));
+    assert_eq!(kinds!(1.5, (a)), ("float", "paren"));
+}
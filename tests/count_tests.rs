@@ -0,0 +1,40 @@
+use cps::cps;
+
+#[cps]
+macro_rules! counts {
+    ($($x:tt)*) =>
+    let $tts:literal = cps::count_tts!($($x)*) in
+    let $items:literal = cps::count_items!($($x)*) in
+    {
+        ($tts, $items)
+    };
+}
+
+#[cps]
+macro_rules! lengths {
+    ($s:literal) =>
+    let $chars:literal = cps::str_len!($s) in
+    let $bytes:literal = cps::str_byte_len!($s) in
+    {
+        ($chars, $bytes)
+    };
+}
+
+#[test]
+fn count_tokens() {
+    assert_eq!(counts!(), (0, 0));
+    assert_eq!(counts!(a, b c, (d, e),), (7, 3));
+    assert_eq!(counts!('a, &'static str), (5, 2));
+}
+
+#[test]
+fn string_lengths() {
+    assert_eq!(lengths!(""), (0, 0));
+    assert_eq!(lengths!("héllo"), (5, 6));
+}
+
+#[test]
+fn counts_are_usable_as_lengths() {
+    let array = [0u8; counts!(a b c).0];
+    assert_eq!(array.len(), 3);
+}
@@ -0,0 +1,110 @@
+use cps::cps;
+
+#[cps]
+macro_rules! macro1 {
+    () => { BaseCase };
+    (a) => { CaseA };
+    (b) => { CaseB };
+    (CaseA) => { MatchedCaseA };
+    (next) => { macro1 };
+}
+
+#[cps]
+macro_rules! macro2 {
+    (body) => {
+        stringify!(cps::eager!(macro1!(a)) cps::eager!(macro1!(b)))
+    };
+
+    (nested) => {
+        cps::eager!(cps::stringify!(cps::eager!(macro1!(cps::eager!(macro1!(a))))))
+    };
+
+    (argument $x:tt) =>
+    let $y:tt = cps::stringify!($x cps::eager!(macro1!($x))) in
+    {
+        $y
+    };
+
+    (after let) =>
+    let $x:ident = macro1!(next) in
+    {
+        stringify!(cps::eager!($x!(a)))
+    };
+
+    (nested eval $x:tt) => {
+        cps::eval! {
+            let $$y:tt = macro1!($x) in
+            { stringify!(cps::eager!(macro1!($$y))) }
+        }
+    };
+}
+
+#[test]
+fn eager_in_body() {
+    assert_eq!(macro2!(body), "CaseA CaseB");
+}
+
+#[test]
+fn nested_eager() {
+    assert_eq!(macro2!(nested), "MatchedCaseA");
+}
+
+#[test]
+fn eager_in_argument() {
+    assert_eq!(macro2!(argument a), "a CaseA");
+}
+
+#[test]
+fn eager_after_let() {
+    assert_eq!(macro2!(after let), "CaseA");
+}
+
+#[test]
+fn eager_in_nested_eval() {
+    assert_eq!(macro2!(nested eval a), "MatchedCaseA");
+}
+
+#[test]
+fn eager_in_eval() {
+    let x = cps::eval! {
+        let $x:tt = cps::stringify!(cps::eager!(macro1!())) in
+        { concat!($x, cps::eager!(cps::stringify!(cps::eager!(macro1!(b))))) }
+    };
+    assert_eq!(x, "BaseCaseCaseB");
+}
+
+#[cps]
+macro_rules! macro3 {
+    (composed) =>
+    let $x:tt = cps::stringify!(macro1!!(macro1!!(a))) in
+    {
+        $x
+    };
+
+    (arguments $cont:ident) =>
+    let $x:tt = cps::concat!(cps::stringify!!(macro1!!(a)), "-", cps::stringify!!($cont!!(b))) in
+    {
+        $x
+    };
+
+    (unevaluated) =>
+    let $x:tt = cps::stringify!(macro1!(a)) in
+    {
+        $x
+    };
+}
+
+#[test]
+fn double_bang_composition() {
+    assert_eq!(macro3!(composed), "MatchedCaseA");
+}
+
+#[test]
+fn double_bang_arguments() {
+    assert_eq!(macro3!(arguments macro1), "CaseA-CaseB");
+}
+
+#[test]
+fn single_bang_is_not_evaluated() {
+    assert_eq!(macro3!(unevaluated), "macro1! (a)");
+}
@@ -0,0 +1,29 @@
+use cps::cps;
+
+#[cps]
+macro_rules! package {
+    (@or_default Some($value:literal)) => { $value };
+    (@or_default None) => { "default" };
+
+    ($var:literal) =>
+    let $name:literal = cps::env!("CARGO_PKG_NAME") in
+    let $($value:tt)* = cps::option_env!($var) in
+    let $result:literal = package!(@or_default $($value)*) in
+    {
+        ($name, $result)
+    };
+}
+
+#[test]
+fn cargo_variables() {
+    assert_eq!(package!("CARGO_PKG_VERSION"), ("cps", env!("CARGO_PKG_VERSION")));
+    assert_eq!(
+        package!("CARGO_MANIFEST_DIR"),
+        ("cps", env!("CARGO_MANIFEST_DIR"))
+    );
+}
+
+#[test]
+fn missing_variables() {
+    assert_eq!(package!("CPS_TEST_VARIABLE_THAT_IS_NOT_SET"), ("cps", "default"));
+}
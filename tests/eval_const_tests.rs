@@ -0,0 +1,37 @@
+use cps::cps;
+
+#[cps]
+macro_rules! table {
+    (@size true) => { "Large" };
+    (@size false) => { "Small" };
+
+    ($width:literal, $height:literal) =>
+    let $area:literal = cps::eval_const!($width * $height) in
+    let $offset:literal = cps::eval_const!(($area - 1) / 2 + ($width as usize)) in
+    let $large:tt = cps::eval_const!($area >= 16 && $width != $height) in
+    let $size:literal = table!(@size $large) in
+    {
+        ($area, $offset, $size)
+    };
+}
+
+#[cps]
+macro_rules! compare_strings {
+    ($a:literal, $b:literal) =>
+    let $less:tt = cps::eval_const!($a < $b) in
+    let $first:tt = cps::eval_const!('a' as u8 + 1) in
+    {
+        ($less, $first)
+    };
+}
+
+#[test]
+fn table_sizes() {
+    assert_eq!(table!(4usize, 5usize), (20usize, 13usize, "Large"));
+    assert_eq!(table!(2, 3), (6, 4usize, "Small"));
+}
+
+#[test]
+fn strings_and_chars() {
+    assert_eq!(compare_strings!("apple", "banana"), (true, 98u8));
+}
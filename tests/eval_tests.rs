@@ -0,0 +1,116 @@
+use cps::cps;
+
+#[cps]
+macro_rules! macro1 {
+    () => { BaseCase };
+    (type) => { u64 };
+    (function) => { from_eval };
+    (list) => { a, b, c };
+    (next) => { macro1 };
+}
+
+#[cps]
+macro_rules! macro2 {
+    ($x:expr) =>
+    let $y:tt = macro1!() in
+    {
+        cps::eval! {
+            let $$z:tt = macro1!() in
+            { concat!($x, stringify!($y), stringify!($$z)) }
+        }
+    };
+}
+
+type Evaluated = cps::eval! {
+    let $t:ty = macro1!(type) in
+    { Vec<$t> }
+};
+
+cps::eval! {
+    let $name:ident = macro1!(function) in
+    {
+        fn $name() -> &'static str {
+            "FromEval"
+        }
+    }
+}
+
+#[test]
+fn expression_position() {
+    let x = cps::eval! {
+        let $x:tt = macro1!() in
+        let $y:tt = cps::stringify!($x) in
+        { $y }
+    };
+    assert_eq!(x, "BaseCase");
+}
+
+#[test]
+fn type_position() {
+    let v: Evaluated = vec![1, 2];
+    assert_eq!(v, vec![1u64, 2]);
+}
+
+fn return_type() -> cps::eval! {
+    let $t:ty = macro1!(type) in
+    { Option<$t> }
+} {
+    Some(3)
+}
+
+#[test]
+fn return_type_position() {
+    assert_eq!(return_type(), Some(3u64));
+}
+
+#[test]
+fn item_position() {
+    assert_eq!(from_eval(), "FromEval");
+}
+
+#[test]
+fn repetitions() {
+    let x = cps::eval! {
+        let $($x:ident),* = macro1!(list) in
+        { [$(stringify!($x)),*] }
+    };
+    assert_eq!(x, ["a", "b", "c"]);
+}
+
+#[test]
+fn indirection() {
+    let x = cps::eval! {
+        let $cont:ident = macro1!(next) in
+        let $x:tt = $cont!() in
+        { stringify!($x) }
+    };
+    assert_eq!(x, "BaseCase");
+}
+
+#[test]
+fn nested_in_cps_macro() {
+    assert_eq!(macro2!("Got: "), "Got: BaseCaseBaseCase");
+}
+
+#[test]
+fn crate_path() {
+    mod facade {
+        pub use cps::{eval, stringify};
+    }
+
+    let x = cps::eval! {
+        #![cps(crate = "facade")]
+        let $x:tt = cps::stringify!(BaseCase) in
+        { $x }
+    };
+    assert_eq!(x, "BaseCase");
+}
+
+#[test]
+fn format_in_eval() {
+    let x = cps::eval! {
+        let $x:tt = cps::format!("{}-{}", 1, "a") in
+        { $x }
+    };
+    assert_eq!(x, "1-a");
+}
@@ -0,0 +1,44 @@
+use cps::cps;
+
+#[cps]
+macro_rules! getter_name {
+    ($field:ident, $index:literal) =>
+    let $name:literal = cps::format!("get_{field}_{:02}", $index, field = $field) in
+    {
+        $name
+    };
+}
+
+#[cps]
+macro_rules! table_row {
+    ($label:literal, $value:literal) =>
+    let $row:literal = cps::format!("|{:<8}|{:>6.2}|{:#x}|", $label, $value, 255u8) in
+    {
+        $row
+    };
+}
+
+#[cps]
+macro_rules! make_fn {
+    ($name:ident) =>
+    let $fn_name:literal = cps::format!("{}_{}", $name, suffix) in
+    let $fn_ident:ident = cps::unstringify!($fn_name) in
+    {
+        fn $fn_ident() -> &'static str {
+            $fn_name
+        }
+    };
+}
+
+make_fn!(generated);
+
+#[test]
+fn formats() {
+    assert_eq!(getter_name!(width, 7), "get_width_07");
+    assert_eq!(table_row!("pi", 3.14159), "|pi      |  3.14|0xff|");
+}
+
+#[test]
+fn builds_identifiers() {
+    assert_eq!(generated_suffix(), "generated_suffix");
+}
@@ -0,0 +1,30 @@
+use cps::cps;
+
+// Metavariables with the same names as those used internally by generated rules
+#[cps]
+macro_rules! macro1 {
+    (a) => { CaseA };
+
+    ($_cps_next_head:tt $_cps_stack:tt) =>
+    let $_cps_res0:tt = macro1!($_cps_next_head) in
+    let $unexpected:tt = cps::stringify!($_cps_res0) in
+    {
+        concat!($unexpected, stringify!($_cps_stack))
+    };
+
+    ($($input:tt)*) =>
+    let $($_cps_arg0:tt)* = macro1!(a b) in
+    {
+        concat!($($_cps_arg0)*, $(stringify!($input)),*)
+    };
+}
+
+#[test]
+fn internal_names_as_arm_and_binding_names() {
+    assert_eq!(macro1!(a b), "CaseAb");
+}
+
+#[test]
+fn internal_names_in_repetitions() {
+    assert_eq!(macro1!(c d e), "CaseAbcde");
+}
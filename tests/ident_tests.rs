@@ -0,0 +1,60 @@
+use cps::cps;
+
+#[cps]
+macro_rules! make_fn {
+    ($field:ident) =>
+    let $name:ident = cps::ident!(get_, $field) in
+    {
+        fn $name() -> &'static str {
+            stringify!($name)
+        }
+    };
+
+    ($prefix:literal $field:ident $n:literal) =>
+    let $name:ident = cps::ident!($prefix, $field, '_', $n) in
+    {
+        fn $name() -> &'static str {
+            stringify!($name)
+        }
+    };
+}
+
+make_fn!(value);
+make_fn!("set_" value 12u8);
+
+#[cps]
+macro_rules! make_local {
+    ($x:ident) =>
+    let $name:ident = cps::ident!(#[span($x)] $x, _copy) in
+    {
+        let $name = $x;
+    };
+}
+
+#[cps]
+macro_rules! raw {
+    ($x:tt) =>
+    let $name:ident = cps::ident!(#[raw] $x) in
+    {
+        stringify!($name)
+    };
+}
+
+#[test]
+fn idents_from_parts() {
+    assert_eq!(get_value(), "get_value");
+    assert_eq!(set_value_12(), "set_value_12");
+}
+
+#[test]
+fn span_from_input() {
+    let x = 5;
+    make_local!(x);
+    assert_eq!(x_copy, 5);
+}
+
+#[test]
+fn raw_idents() {
+    assert_eq!(raw!("match"), "r#match");
+    assert_eq!(raw!(foo), "r#foo");
+}
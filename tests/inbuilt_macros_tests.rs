@@ -24,17 +24,29 @@ macro_rules! macro1 {
     };
 
     (@run include) =>
-    let $($y:tt)* = cps::include!("tests/test_file.txt") in
+    let $($y:tt)* = cps::include!("test_file.txt") in
     let $z:tt = cps::stringify!($($y)*) in
     {
         $z
     };
 
     (@run include_str) =>
-    let $x:literal = cps::include_str!("tests/test_file.txt") in
+    let $x:literal = cps::include_str!("test_file.txt") in
     {
         $x
     };
+
+    (@run include_bytes) =>
+    let $x:literal = cps::include_bytes!(#[manifest_dir] "tests/test_file.txt") in
+    {
+        $x
+    };
+
+    (@run include_bytes_list) =>
+    let $($x:literal),* = cps::include_bytes_list!("./test_file.txt") in
+    {
+        [$($x),*]
+    };
 }
 
 #[test]
@@ -62,3 +74,19 @@ fn include_str_call() {
         "this is a test file\nused for include macros testing!"
     );
 }
+
+#[test]
+fn include_bytes_call() {
+    assert_eq!(
+        macro1!(@run include_bytes),
+        b"this is a test file\nused for include macros testing!"
+    );
+}
+
+#[test]
+fn include_bytes_list_call() {
+    assert_eq!(
+        &macro1!(@run include_bytes_list),
+        b"this is a test file\nused for include macros testing!"
+    );
+}
@@ -0,0 +1,57 @@
+use cps::cps;
+
+#[cps]
+macro_rules! classify {
+    (@choose true) => { "Keyword" };
+    (@choose false) => { "Other" };
+
+    ($name:ident) =>
+    let $is_fn:tt = cps::ident_eq!($name, r#fn) in
+    let $is_let:tt = cps::ident_eq!($name, let) in
+    let $is_keyword:tt = cps::or!($is_fn, $is_let) in
+    let $result:literal = classify!(@choose $is_keyword) in
+    {
+        $result
+    };
+}
+
+#[cps]
+macro_rules! same {
+    ($a:tt, $b:tt) =>
+    let $eq:tt = cps::eq!($a, $b) in
+    let $ne:tt = cps::ne!($a, $b) in
+    let $both:tt = cps::and!($eq, cps::eager!(cps::not!($ne))) in
+    {
+        ($eq, $ne, $both)
+    };
+}
+
+#[cps]
+macro_rules! ordered {
+    ($a:literal, $b:literal) =>
+    let $lt:tt = cps::lt!($a, $b) in
+    let $ge:tt = cps::ge!($a, $b) in
+    {
+        ($lt, $ge)
+    };
+}
+
+#[test]
+fn identifiers() {
+    assert_eq!(classify!(r#fn), "Keyword");
+    assert_eq!(classify!(r#let), "Keyword");
+    assert_eq!(classify!(foo), "Other");
+}
+
+#[test]
+fn token_equality() {
+    assert_eq!(same!((a::b, [c]), (a :: b , [ c ])), (true, false, true));
+    assert_eq!(same!((a), [a]), (false, true, false));
+}
+
+#[test]
+fn literal_ordering() {
+    assert_eq!(ordered!("apple", "banana"), (true, false));
+    assert_eq!(ordered!('z', 'a'), (false, true));
+    assert_eq!(ordered!(1.5, 1.25), (false, true));
+}
@@ -0,0 +1,60 @@
+#[cps::cps]
+#[macro_use]
+mod my_macros {
+    macro_rules! macro1 {
+        (a) => { CaseA };
+        (b) => { CaseB };
+        ($($x:ident),+ ; $e:expr) => { [$(stringify!($x)),+, $e] };
+    }
+
+    macro_rules! macro2 {
+        () =>
+        let $x:tt = macro1!(a) in
+        let $y:tt = macro1!(b) in
+        {
+            concat!(stringify!($x), stringify!($y))
+        };
+
+        ($i:ident) =>
+        let $($x:tt)* = macro1!(c, $i ; "e") in
+        {
+            $($x)*
+        };
+    }
+
+    macro_rules! lifetime_names {
+        ($a:tt $b:tt) => { [stringify!($a), stringify!($b)] };
+    }
+
+    #[cps(crate = "::cps")]
+    macro_rules! lifetimes {
+        () =>
+        let $x:tt = lifetime_names!('a 'static) in
+        {
+            $x
+        };
+    }
+
+    pub(crate) fn case_a_b() -> &'static str {
+        macro2!()
+    }
+
+    pub(crate) fn lifetime_args() -> [&'static str; 2] {
+        lifetimes!()
+    }
+}
+
+#[test]
+fn module_macros_call_each_other() {
+    assert_eq!(my_macros::case_a_b(), "CaseACaseB");
+}
+
+#[test]
+fn module_macros_usable_after_module() {
+    assert_eq!(macro2!(d), ["c", "d", "e"]);
+}
+
+#[test]
+fn module_macros_take_lifetimes_as_token_trees() {
+    assert_eq!(my_macros::lifetime_args(), ["'a", "'static"]);
+}
@@ -17,7 +17,7 @@ macro_rules! exported_macro1 {
     () => { BaseCase };
 
     (stringify) =>
-    let $x:tt = crate::exported_macro1!() in
+    let $x:tt = $crate::exported_macro1!() in
     {
         stringify!($x)
     };
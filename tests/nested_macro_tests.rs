@@ -0,0 +1,54 @@
+use cps::cps;
+
+#[cps]
+macro_rules! macro1 {
+    () => { BaseCase };
+}
+
+// `$$` in a rule body produces a `$` in the output, so bodies can define macros with their own metavariables
+#[cps]
+macro_rules! make_macro {
+    ($name:ident) =>
+    let $x:tt = macro1!() in
+    {
+        macro_rules! $name {
+            ($$($$y:expr),*) => {
+                [stringify!($x) $$(, stringify!($$y))*]
+            };
+        }
+    };
+}
+
+make_macro!(generated_macro);
+
+#[cps]
+macro_rules! make_cps_macro {
+    ($name:ident) =>
+    let $x:tt = macro1!() in
+    let $s:tt = cps::stringify!($x) in
+    {
+        #[cps::cps]
+        macro_rules! $name {
+            () => { $s };
+
+            ($$($$y:tt)*) =>
+            let $$z:tt = $name!() in
+            {
+                concat!($$z $$(, stringify!($$y))*)
+            };
+        }
+    };
+}
+
+make_cps_macro!(generated_cps_macro);
+
+#[test]
+fn nested_macro_definition() {
+    assert_eq!(generated_macro!(1, 2), ["BaseCase", "1", "2"]);
+}
+
+#[test]
+fn nested_cps_macro_definition() {
+    assert_eq!(generated_cps_macro!(), "BaseCase");
+    assert_eq!(generated_cps_macro!(a b), "BaseCaseab");
+}
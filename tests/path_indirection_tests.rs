@@ -0,0 +1,55 @@
+use cps::cps;
+
+#[allow(clippy::single_component_path_imports)]
+mod helpers {
+    #[cps::cps]
+    macro_rules! helper {
+        () => { BaseCase };
+        (next) => { helpers::other_helper };
+    }
+
+    #[cps::cps]
+    macro_rules! other_helper {
+        () => { OtherCase };
+    }
+
+    pub(crate) use helper;
+    pub(crate) use other_helper;
+}
+
+#[cps]
+macro_rules! macro1 {
+    (in module $module:path) =>
+    let $x:tt = $module::helper!() in
+    {
+        stringify!($x)
+    };
+
+    (via $cont1:path) =>
+    let $cont2:path = $cont1!(next) in
+    let $x:tt = $cont2!() in
+    {
+        stringify!($x)
+    };
+
+    ($cont:path) =>
+    let $x:tt = $cont!() in
+    {
+        stringify!($x)
+    };
+}
+
+#[test]
+fn path_from_input() {
+    assert_eq!(macro1!(helpers::helper), "BaseCase");
+}
+
+#[test]
+fn module_path_from_input() {
+    assert_eq!(macro1!(in module helpers), "BaseCase");
+}
+
+#[test]
+fn path_from_let_binding() {
+    assert_eq!(macro1!(via helpers::helper), "OtherCase");
+}
@@ -0,0 +1,49 @@
+use cps::cps;
+
+#[cps]
+macro_rules! list {
+    (@sql) =>
+    let $($file:literal)* = cps::read_dir!("read_dir", "*.sql") in
+    {
+        [$($file),*]
+    };
+
+    (@all) =>
+    let $($file:literal)* = cps::read_dir!(#[recursive] "read_dir") in
+    {
+        [$($file),*]
+    };
+
+    (@contents) =>
+    let $($path:literal)* = cps::read_dir!(#[recursive] #[paths] "read_dir", "*_drop.sql") in
+    let $($contents:literal)* = list!(@include $($path)*) in
+    {
+        [$($contents),*]
+    };
+
+    (@include) => {};
+    (@include $path:literal $($rest:literal)*) =>
+    let $contents:literal = cps::include_str!($path) in
+    let $($others:literal)* = list!(@include $($rest)*) in
+    {
+        $contents $($others)*
+    };
+}
+
+#[test]
+fn matching_files() {
+    assert_eq!(list!(@sql), ["a_create.sql", "b_create.sql"]);
+}
+
+#[test]
+fn recursive() {
+    assert_eq!(
+        list!(@all),
+        ["a_create.sql", "b_create.sql", "nested/c_drop.sql", "notes.txt"]
+    );
+}
+
+#[test]
+fn paths() {
+    assert_eq!(list!(@contents), ["DROP TABLE a;\n"]);
+}
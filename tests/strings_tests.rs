@@ -0,0 +1,52 @@
+use cps::cps;
+
+#[cps]
+macro_rules! words {
+    ($s:literal) =>
+    let $trimmed:literal = cps::str_trim!($s) in
+    let $($word:literal),* = cps::str_split!($trimmed, " ") in
+    {
+        [$($word),*]
+    };
+}
+
+#[cps]
+macro_rules! transform {
+    ($s:literal) =>
+    let $upper:literal = cps::str_to_upper!($s) in
+    let $lower:literal = cps::str_to_lower!($s) in
+    let $replaced:literal = cps::str_replace!($s, "l", "L") in
+    let $sub:literal = cps::str_substr!($s, 1, 4) in
+    let $repeated:literal = cps::str_repeat!($s, 2) in
+    {
+        [$upper, $lower, $replaced, $sub, $repeated]
+    };
+}
+
+#[cps]
+macro_rules! search {
+    ($s:literal, $pattern:literal) =>
+    let $starts:tt = cps::str_starts_with!($s, $pattern) in
+    let $ends:tt = cps::str_ends_with!($s, $pattern) in
+    let $contains:tt = cps::str_contains!($s, $pattern) in
+    {
+        [$starts, $ends, $contains]
+    };
+}
+
+#[test]
+fn splitting() {
+    assert_eq!(words!("  the quick fox "), ["the", "quick", "fox"]);
+    assert_eq!(words!(r"a\b"), [r"a\b"]);
+}
+
+#[test]
+fn transforms() {
+    assert_eq!(transform!("Hello"), ["HELLO", "hello", "HeLLo", "ell", "HelloHello"]);
+}
+
+#[test]
+fn searching() {
+    assert_eq!(search!("abcab", "ab"), [true, true, true]);
+    assert_eq!(search!("abc", "\u{62}"), [false, false, true]);
+}
@@ -0,0 +1,32 @@
+use cps::cps;
+
+#[cps]
+macro_rules! build_expr {
+    ($lhs:literal, $op:literal, $rhs:literal) =>
+    let $source:literal = cps::concat!($lhs, " ", $op, " ", $rhs) in
+    let $($expr:tt)* = cps::unstringify!($source) in
+    {
+        $($expr)*
+    };
+}
+
+#[cps]
+macro_rules! round_trip {
+    ($($x:tt)*) =>
+    let $string:literal = cps::stringify!($($x)*) in
+    let $($tokens:tt)* = cps::unstringify!($string) in
+    {
+        [$($tokens),*]
+    };
+}
+
+#[test]
+fn expressions() {
+    assert_eq!(build_expr!(2, "*", 21), 42);
+    assert_eq!(build_expr!("(1", "+", "2)"), 3);
+}
+
+#[test]
+fn stringify_round_trip() {
+    assert_eq!(round_trip!(1 2 3), [1, 2, 3]);
+}